@@ -2,3 +2,132 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+// DEFAULT VOLATILITY PRIORS FOR NEWLY REGISTERED STRATEGIES (0-10000 scale)
+// Applied until the first update_performance call supplies real risk data
+#[constant]
+pub const DEFAULT_VOLATILITY_STABLE_LENDING: u32 = 1500;   // 15% - overcollateralized lending
+
+#[constant]
+pub const DEFAULT_VOLATILITY_YIELD_FARMING: u32 = 6000;    // 60% - LP exposure + impermanent loss
+
+#[constant]
+pub const DEFAULT_VOLATILITY_LIQUID_STAKING: u32 = 2500;   // 25% - SOL-denominated staking
+
+// PERFORMANCE SCORE SMOOTHING
+// Weight of the newest score in the EMA (basis points); remainder carries the prior value
+#[constant]
+pub const SCORE_EMA_ALPHA_BPS: u64 = 3000;                  // 30% new, 70% history
+
+// REBALANCE ELIGIBILITY REASON CODES (RebalanceEligibilityEvent.reason_code)
+#[constant]
+pub const REBALANCE_ELIGIBLE: u8 = 0;
+
+#[constant]
+pub const REBALANCE_BLOCKED_PAUSED: u8 = 1;                 // emergency_pause set
+
+#[constant]
+pub const REBALANCE_BLOCKED_INTERVAL: u8 = 2;               // min_rebalance_interval not elapsed
+
+#[constant]
+pub const REBALANCE_BLOCKED_TOO_FEW_STRATEGIES: u8 = 3;     // fewer than 2 strategies registered
+
+// ALLOCATION PLAN VALIDATION ISSUE CODES (PlanValidationEvent.issue_code)
+#[constant]
+pub const PLAN_VALID: u8 = 0;
+
+#[constant]
+pub const PLAN_INVALID_BATCH_SIZE: u8 = 1;                  // empty or more than 20 allocations
+
+#[constant]
+pub const PLAN_DUPLICATE_STRATEGY: u8 = 2;                  // same strategy_id allocated twice
+
+#[constant]
+pub const PLAN_INVALID_AMOUNT: u8 = 3;                      // zero amount or overflow
+
+#[constant]
+pub const PLAN_ABOVE_MAX_SINGLE_STRATEGY: u8 = 4;           // strategy share over max_single_strategy_bps
+
+#[constant]
+pub const PLAN_BELOW_MIN_SINGLE_STRATEGY: u8 = 5;           // strategy share under min_single_strategy_bps
+
+// PORTFOLIO CREATION RATE LIMIT (per payer)
+#[constant]
+pub const MAX_PORTFOLIOS_PER_WINDOW: u8 = 10;
+
+#[constant]
+pub const PORTFOLIO_CREATION_WINDOW: i64 = 86400;           // 24 hours
+
+// RENT-SAFE RESIDUAL KEPT BEHIND ON CAPITAL MOVES (on top of the rent-exempt minimum)
+#[constant]
+pub const RENT_KEEP_BUFFER_LAMPORTS: u64 = 1_000_000;       // 0.001 SOL
+
+// STALE PORTFOLIO DETECTION
+#[constant]
+pub const DEFAULT_STALE_WINDOW: u32 = 7_776_000;            // 90 days without a rebalance
+
+// DEFAULT BALANCE NORMALIZATION BAND FOR PERFORMANCE SCORING
+#[constant]
+pub const DEFAULT_BALANCE_FLOOR_LAMPORTS: u64 = 100_000_000;        // 0.1 SOL
+
+#[constant]
+pub const DEFAULT_BALANCE_CAP_LAMPORTS: u64 = 100_000_000_000;      // 100 SOL
+
+// DEFAULT YIELD NORMALIZATION CEILING FOR PERFORMANCE SCORING
+#[constant]
+pub const DEFAULT_YIELD_CEILING_BPS: u32 = 50_000;                  // 500% APY
+
+// FEE DESTINATION WHITELIST
+#[constant]
+pub const MAX_AUTHORIZED_TREASURIES: usize = 4;
+
+// PERFORMANCE FEE UPPER BOUND
+#[constant]
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 3000;              // 30%
+
+#[constant]
+pub const DEFAULT_PERFORMANCE_FEE_BPS: u16 = 200;           // 2%
+
+// STRATEGY REGISTRY PAGINATION
+#[constant]
+pub const REGISTRY_PAGE_CAPACITY: usize = 32;
+
+// COOLDOWN BEFORE AN EXTRACTED STRATEGY MAY BE RE-FUNDED
+#[constant]
+pub const DEFAULT_EXTRACTION_COOLDOWN: u32 = 86_400;        // 24 hours
+
+// MAXIMUM ALLOCATIONS PER REDISTRIBUTION
+#[constant]
+pub const MAX_REDISTRIBUTION_ALLOCATIONS: usize = 20;
+
+// MINIMUM VOLATILITY ASSUMED BY PERFORMANCE SCORING
+#[constant]
+pub const DEFAULT_VOLATILITY_FLOOR: u32 = 100;              // 1%
+
+// PENDING DEPOSITS HELD PER STRATEGY QUEUE
+#[constant]
+pub const MAX_QUEUED_DEPOSITS: usize = 16;
+
+// ALLOCATIONS APPLIED PER REDISTRIBUTION TRANSACTION (longer plans resume via a cursor)
+#[constant]
+pub const MAX_ALLOCATIONS_PER_TX: usize = 10;
+
+// BALANCE ABOVE WHICH A STRATEGY COUNTS AS FUNDED (matches the rebalancing dust threshold)
+#[constant]
+pub const FUNDED_DUST_FLOOR_LAMPORTS: u64 = 50_000_000;     // 0.05 SOL
+
+// EMERGENCY REBALANCE COOLDOWN (must stay below min_rebalance_interval)
+#[constant]
+pub const DEFAULT_EMERGENCY_COOLDOWN: u32 = 300;            // 5 minutes
+
+// STRATEGY SUMMARIES HELD IN ONE PACKED RANKING ACCOUNT
+#[constant]
+pub const MAX_PACKED_SUMMARIES: usize = 128;
+
+// REPORTING CURRENCY LABEL USED WHEN NONE IS GIVEN
+#[constant]
+pub const DEFAULT_BASE_CURRENCY: [u8; 8] = *b"SOL\0\0\0\0\0";
+
+// SHARE OF THE REPORTED YIELD SHOWN FOR A ZERO-SCORE STRATEGY IN DISPLAY APY
+#[constant]
+pub const DISPLAY_APY_SCORE_FLOOR_BPS: u64 = 5000;          // 50% at score 0, 100% at score 10000
@@ -109,4 +109,103 @@ pub enum RebalancerError {
 
     #[msg("Invalid performance score for calculation")]
     InvalidPerformanceScore,
+
+    #[msg("Invalid or mismatched capital position account")]
+    InvalidPosition,
+
+    #[msg("Strategy status transition not allowed")]
+    InvalidStatusTransition,
+
+    #[msg("Strategy vault account required for funded registration")]
+    VaultRequired,
+
+    #[msg("Transferred funds do not match the declared balance")]
+    FundingMismatch,
+
+    #[msg("No performance data available for ranking (all scores are zero)")]
+    NoPerformanceData,
+
+    #[msg("Strategy accounts provided do not match the claimed strategy count")]
+    StrategyCountMismatch,
+
+    #[msg("Performance update arrived before the minimum update interval elapsed")]
+    UpdateTooFrequent,
+
+    #[msg("Portfolio creation rate limit reached for this authority")]
+    CreationRateLimited,
+
+    #[msg("Strategy is pinned and cannot be rebalanced")]
+    StrategyPinned,
+
+    #[msg("Quote price must be positive for non-SOL denominated strategies")]
+    InvalidQuotePrice,
+
+    #[msg("Balance normalization floor must be positive and below the cap")]
+    InvalidBalanceBand,
+
+    #[msg("Fee destination is not an authorized treasury")]
+    InvalidTreasury,
+
+    #[msg("Treasury whitelist is full")]
+    TreasuryWhitelistFull,
+
+    #[msg("Signer is not a guardian or guardian set is invalid")]
+    InvalidGuardian,
+
+    #[msg("Guardian threshold must be between 1 and the number of guardians")]
+    InvalidGuardianThreshold,
+
+    #[msg("Performance fee exceeds the 30% maximum")]
+    InvalidPerformanceFee,
+
+    #[msg("Deposit would push portfolio AUM past its cap")]
+    AumCapExceeded,
+
+    #[msg("Stored performance score does not match the strategy's metrics")]
+    ScoreMismatch,
+
+    #[msg("Strategy registry page is full")]
+    RegistryPageFull,
+
+    #[msg("Safe harbor must be a listed StableLending strategy with a 1-100% volatility trigger")]
+    InvalidSafeHarbor,
+
+    #[msg("Strategy is at its deposit cap and no deposit queue was provided")]
+    StrategyAtCapacity,
+
+    #[msg("Deposit queue is full")]
+    DepositQueueFull,
+
+    #[msg("Allocation plan does not match the partially applied plan")]
+    AllocationPlanMismatch,
+
+    #[msg("Strategy summary pack is full")]
+    SummaryPackFull,
+
+    #[msg("Base currency must be 1-8 uppercase ASCII letters or digits, zero-padded")]
+    InvalidBaseCurrency,
+
+    #[msg("A ranking pass is already in progress for this portfolio")]
+    RankingInProgress,
+
+    #[msg("Allocation amount must be greater than zero")]
+    ZeroAllocationAmount,
+
+    #[msg("No manager transfer is pending")]
+    NoPendingManagerTransfer,
+
+    #[msg("Deposits are queued for this strategy; the deposit queue account is required")]
+    DepositQueueRequired,
+
+    #[msg("No queued deposit at that position")]
+    QueuedDepositNotFound,
+
+    #[msg("No partially applied allocation plan to abandon")]
+    NoAllocationPlanInProgress,
+
+    #[msg("Fee routing cannot change while an allocation plan is partially applied")]
+    AllocationPlanInProgress,
+
+    #[msg("Withdrawal would leave the vault with a balance below rent exemption; withdraw less or drain it fully")]
+    VaultBelowRentExempt,
 }
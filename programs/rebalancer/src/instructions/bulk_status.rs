@@ -0,0 +1,419 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use super::strategy_accounts::*;
+
+#[derive(Accounts)]
+pub struct BulkStrategyStatus<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    // remaining_accounts: writable Strategy PDAs belonging to this portfolio
+}
+
+pub fn bulk_deprecate<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+    rank_cutoff: u8,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+
+    // INPUT VALIDATIONS
+    require!(rank_cutoff > 0 && rank_cutoff <= 100, RebalancerError::InvalidAllocationPercentage);
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut deprecated_count = 0u32;
+
+    for account_info in ctx.remaining_accounts.iter() {
+        require!(account_info.is_writable, RebalancerError::StrategyNotFound);
+        let mut strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+
+        if deprecate_if_below_cutoff(&mut strategy, rank_cutoff)? {
+            strategy.exit(ctx.program_id)?;
+            deprecated_count = deprecated_count
+                .checked_add(1)
+                .ok_or(RebalancerError::MathOverflow)?;
+            msg!("Strategy {} deprecated (rank {}% < cutoff {}%)",
+                 strategy.strategy_id, strategy.percentile_rank, rank_cutoff);
+        }
+    }
+
+    msg!("Bulk deprecation completed: {} of {} strategies deprecated",
+         deprecated_count, ctx.remaining_accounts.len());
+
+    emit!(BulkDeprecationEvent {
+        portfolio: portfolio_key,
+        rank_cutoff,
+        strategies_checked: ctx.remaining_accounts.len() as u32,
+        deprecated_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Deprecates an Active strategy ranked strictly below the cutoff.
+// Returns whether the status changed.
+pub fn deprecate_if_below_cutoff(strategy: &mut Strategy, rank_cutoff: u8) -> Result<bool> {
+    if strategy.status != StrategyStatus::Active || strategy.percentile_rank >= rank_cutoff {
+        return Ok(false);
+    }
+
+    strategy.transition_status(StrategyStatus::Deprecated)?;
+    Ok(true)
+}
+
+// Deprecates the passed strategies whose rank has stayed below the portfolio's
+// deprecation_rank_cutoff for deprecation_low_rank_cycles rankings in a row
+pub fn flag_for_deprecation<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let config = ctx.accounts.portfolio.config;
+
+    require!(config.deprecation_rank_cutoff > 0, RebalancerError::InvalidAllocationPercentage);
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut deprecated_count = 0u32;
+
+    for account_info in ctx.remaining_accounts.iter() {
+        require!(account_info.is_writable, RebalancerError::StrategyNotFound);
+        let mut strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+
+        if deprecate_if_chronically_low(&mut strategy, config.deprecation_low_rank_cycles)? {
+            strategy.exit(ctx.program_id)?;
+            deprecated_count = deprecated_count
+                .checked_add(1)
+                .ok_or(RebalancerError::MathOverflow)?;
+            msg!("Strategy {} deprecated ({} consecutive rankings below {}%)",
+                 strategy.strategy_id, strategy.consecutive_low_ranks(), config.deprecation_rank_cutoff);
+        }
+    }
+
+    msg!("Deprecation flagging completed: {} of {} strategies deprecated",
+         deprecated_count, ctx.remaining_accounts.len());
+
+    emit!(BulkDeprecationEvent {
+        portfolio: portfolio_key,
+        rank_cutoff: config.deprecation_rank_cutoff,
+        strategies_checked: ctx.remaining_accounts.len() as u32,
+        deprecated_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Deprecates an Active strategy once its consecutive low-rank count reaches required_cycles.
+// Returns whether the status changed.
+pub fn deprecate_if_chronically_low(strategy: &mut Strategy, required_cycles: u8) -> Result<bool> {
+    if strategy.status != StrategyStatus::Active || strategy.consecutive_low_ranks() < required_cycles {
+        return Ok(false);
+    }
+
+    strategy.transition_status(StrategyStatus::Deprecated)?;
+    Ok(true)
+}
+
+pub fn pause_all_strategies<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+) -> Result<()> {
+    bulk_transition(ctx, StrategyStatus::Active, StrategyStatus::Paused)
+}
+
+pub fn resume_all_strategies<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+) -> Result<()> {
+    bulk_transition(ctx, StrategyStatus::Paused, StrategyStatus::Active)
+}
+
+// Moves every passed strategy currently in `from` to `to`; others are left untouched
+fn bulk_transition<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+    from: StrategyStatus,
+    to: StrategyStatus,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut changed_count = 0u32;
+
+    for account_info in ctx.remaining_accounts.iter() {
+        require!(account_info.is_writable, RebalancerError::StrategyNotFound);
+        let mut strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+
+        if transition_if_status(&mut strategy, from, to)? {
+            strategy.exit(ctx.program_id)?;
+            changed_count = changed_count
+                .checked_add(1)
+                .ok_or(RebalancerError::MathOverflow)?;
+        }
+    }
+
+    msg!("Bulk status change completed: {} of {} strategies now {:?}",
+         changed_count, ctx.remaining_accounts.len(), to);
+
+    emit!(BulkStatusChangedEvent {
+        portfolio: portfolio_key,
+        new_status: to,
+        strategies_checked: ctx.remaining_accounts.len() as u32,
+        changed_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Transitions the strategy to `to` only if it is currently in `from`.
+// Returns whether the status changed.
+pub fn transition_if_status(
+    strategy: &mut Strategy,
+    from: StrategyStatus,
+    to: StrategyStatus,
+) -> Result<bool> {
+    if strategy.status != from {
+        return Ok(false);
+    }
+
+    strategy.transition_status(to)?;
+    Ok(true)
+}
+
+#[event]
+pub struct BulkStatusChangedEvent {
+    pub portfolio: Pubkey,
+    pub new_status: StrategyStatus,
+    pub strategies_checked: u32,
+    pub changed_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BulkDeprecationEvent {
+    pub portfolio: Pubkey,
+    pub rank_cutoff: u8,
+    pub strategies_checked: u32,
+    pub deprecated_count: u32,
+    pub timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct StatusBreakdown<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    // remaining_accounts: up to MAX_STRATEGY_BATCH Strategy PDAs of this portfolio
+}
+
+// Read-only view: how many of the passed strategies are in each status
+pub fn status_breakdown<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StatusBreakdown<'info>>,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut statuses = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+        statuses.push(strategy.status);
+    }
+
+    let event = StatusBreakdownEvent::tally(portfolio_key, &statuses, Clock::get()?.unix_timestamp)?;
+
+    msg!("Status breakdown: portfolio={}, active={}, paused={}, deprecated={}",
+         portfolio_key, event.active, event.paused, event.deprecated);
+
+    emit!(event);
+
+    Ok(())
+}
+
+#[event]
+pub struct StatusBreakdownEvent {
+    pub portfolio: Pubkey,
+    pub active: u32,
+    pub paused: u32,
+    pub deprecated: u32,
+    pub timestamp: i64,
+}
+
+impl StatusBreakdownEvent {
+    pub fn tally(portfolio: Pubkey, statuses: &[StrategyStatus], timestamp: i64) -> Result<Self> {
+        let mut event = StatusBreakdownEvent {
+            portfolio,
+            active: 0,
+            paused: 0,
+            deprecated: 0,
+            timestamp,
+        };
+
+        for status in statuses {
+            let count = match status {
+                StrategyStatus::Active => &mut event.active,
+                StrategyStatus::Paused => &mut event.paused,
+                StrategyStatus::Deprecated => &mut event.deprecated,
+            };
+            *count = count.checked_add(1).ok_or(RebalancerError::MathOverflow)?;
+        }
+
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn ranked_strategy(percentile_rank: u8, status: StrategyStatus) -> Strategy {
+        Strategy {
+            current_balance: 1_000_000_000,
+            yield_rate: 5000,
+            volatility_score: 3000,
+            performance_score: 5000,
+            smoothed_score: 5000,
+            percentile_rank,
+            status,
+            ..test_fixtures::strategy()
+        }
+    }
+
+    #[test]
+    fn test_bulk_deprecate_bottom_two_of_five() {
+        let mut strategies: Vec<Strategy> = [100u8, 75, 50, 25, 0]
+            .iter()
+            .map(|&rank| ranked_strategy(rank, StrategyStatus::Active))
+            .collect();
+
+        let deprecated = strategies
+            .iter_mut()
+            .map(|s| deprecate_if_below_cutoff(s, 30).unwrap())
+            .filter(|&changed| changed)
+            .count();
+
+        assert_eq!(deprecated, 2);
+        assert_eq!(strategies[0].status, StrategyStatus::Active);
+        assert_eq!(strategies[1].status, StrategyStatus::Active);
+        assert_eq!(strategies[2].status, StrategyStatus::Active);
+        assert_eq!(strategies[3].status, StrategyStatus::Deprecated);
+        assert_eq!(strategies[4].status, StrategyStatus::Deprecated);
+    }
+
+    #[test]
+    fn test_bulk_deprecate_skips_non_active() {
+        let mut paused = ranked_strategy(0, StrategyStatus::Paused);
+        let mut deprecated = ranked_strategy(0, StrategyStatus::Deprecated);
+
+        assert!(!deprecate_if_below_cutoff(&mut paused, 30).unwrap());
+        assert!(!deprecate_if_below_cutoff(&mut deprecated, 30).unwrap());
+        assert_eq!(paused.status, StrategyStatus::Paused);
+    }
+
+    #[test]
+    fn test_pause_all_then_resume_all() {
+        let mut strategies = [
+            ranked_strategy(90, StrategyStatus::Active),
+            ranked_strategy(50, StrategyStatus::Active),
+            ranked_strategy(10, StrategyStatus::Deprecated),
+        ];
+
+        let paused = strategies
+            .iter_mut()
+            .map(|s| transition_if_status(s, StrategyStatus::Active, StrategyStatus::Paused).unwrap())
+            .filter(|&changed| changed)
+            .count();
+        assert_eq!(paused, 2);
+        assert_eq!(strategies[0].status, StrategyStatus::Paused);
+        assert_eq!(strategies[1].status, StrategyStatus::Paused);
+        assert_eq!(strategies[2].status, StrategyStatus::Deprecated);
+
+        let resumed = strategies
+            .iter_mut()
+            .map(|s| transition_if_status(s, StrategyStatus::Paused, StrategyStatus::Active).unwrap())
+            .filter(|&changed| changed)
+            .count();
+        assert_eq!(resumed, 2);
+        assert_eq!(strategies[0].status, StrategyStatus::Active);
+        assert_eq!(strategies[1].status, StrategyStatus::Active);
+        // Deprecated strategies are never revived
+        assert_eq!(strategies[2].status, StrategyStatus::Deprecated);
+    }
+
+    #[test]
+    fn test_status_transition_rules() {
+        let mut strategy = ranked_strategy(50, StrategyStatus::Active);
+        strategy.transition_status(StrategyStatus::Paused).unwrap();
+        strategy.transition_status(StrategyStatus::Active).unwrap();
+        strategy.transition_status(StrategyStatus::Deprecated).unwrap();
+
+        // Deprecated is terminal
+        assert!(strategy.transition_status(StrategyStatus::Active).is_err());
+        assert!(strategy.transition_status(StrategyStatus::Paused).is_err());
+    }
+
+    #[test]
+    fn test_status_breakdown_counts_mixed_statuses() {
+        let strategies = [
+            ranked_strategy(90, StrategyStatus::Active),
+            ranked_strategy(60, StrategyStatus::Paused),
+            ranked_strategy(40, StrategyStatus::Active),
+            ranked_strategy(10, StrategyStatus::Deprecated),
+            ranked_strategy(80, StrategyStatus::Active),
+            ranked_strategy(5, StrategyStatus::Deprecated),
+        ];
+        let statuses: Vec<StrategyStatus> = strategies.iter().map(|s| s.status).collect();
+
+        let event = StatusBreakdownEvent::tally(Pubkey::new_unique(), &statuses, 0).unwrap();
+        assert_eq!((event.active, event.paused, event.deprecated), (3, 1, 2));
+        assert_eq!(event.active + event.paused + event.deprecated, strategies.len() as u32);
+    }
+
+    #[test]
+    fn test_deprecation_after_consecutive_low_ranks() {
+        let config = PortfolioConfig {
+            deprecation_rank_cutoff: 25,
+            deprecation_low_rank_cycles: 3,
+            ..PortfolioConfig::default()
+        };
+        config.validate().unwrap();
+        let mut strategy = ranked_strategy(50, StrategyStatus::Active);
+
+        // A rank at or above the cutoff breaks the streak
+        for (rank, expected_count) in [(10u8, 1u8), (15, 2), (40, 0), (5, 1), (10, 2)] {
+            strategy.percentile_rank = rank;
+            strategy.track_low_rank(config.deprecation_rank_cutoff);
+            assert_eq!(strategy.consecutive_low_ranks(), expected_count);
+            assert!(!deprecate_if_chronically_low(&mut strategy, config.deprecation_low_rank_cycles).unwrap());
+        }
+        assert_eq!(strategy.status, StrategyStatus::Active);
+
+        // Third low rank in a row reaches the threshold
+        strategy.percentile_rank = 20;
+        strategy.track_low_rank(config.deprecation_rank_cutoff);
+        assert_eq!(strategy.consecutive_low_ranks(), 3);
+        assert!(deprecate_if_chronically_low(&mut strategy, config.deprecation_low_rank_cycles).unwrap());
+        assert_eq!(strategy.status, StrategyStatus::Deprecated);
+        assert!(!deprecate_if_chronically_low(&mut strategy, config.deprecation_low_rank_cycles).unwrap());
+
+        // Tracking off: the counter never builds up
+        let mut untracked = ranked_strategy(0, StrategyStatus::Active);
+        untracked.track_low_rank(0);
+        assert_eq!(untracked.consecutive_low_ranks(), 0);
+        assert!(!untracked.rank_stale()); // Shares reserved bytes without overlap
+    }
+}
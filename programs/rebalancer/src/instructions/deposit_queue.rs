@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use crate::instructions::register_strategy::credit_deposit;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct InitDepositQueue<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = DepositQueue::MAX_SIZE,
+        seeds = [b"deposit_queue", strategy.key().as_ref()],
+        bump
+    )]
+    pub deposit_queue: Account<'info, DepositQueue>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct ProcessDepositQueue<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_queue", strategy.key().as_ref()],
+        bump = deposit_queue.bump
+    )]
+    pub deposit_queue: Account<'info, DepositQueue>,
+
+    /// Strategy capital vault; required when the portfolio enforces funded registration
+    #[account(
+        mut,
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: Option<SystemAccount<'info>>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct CancelQueuedDeposit<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_queue", strategy.key().as_ref()],
+        bump = deposit_queue.bump
+    )]
+    pub deposit_queue: Account<'info, DepositQueue>,
+
+    pub manager: Signer<'info>,
+}
+
+pub fn init_deposit_queue(ctx: Context<InitDepositQueue>, _strategy_id: Pubkey) -> Result<()> {
+    let queue = &mut ctx.accounts.deposit_queue;
+
+    queue.portfolio = ctx.accounts.portfolio.key();
+    queue.strategy = ctx.accounts.strategy.key();
+    queue.count = 0;
+    queue.amounts = [0; 16];
+    queue.bump = ctx.bumps.deposit_queue;
+
+    msg!("Deposit queue created for strategy {}", ctx.accounts.strategy.strategy_id);
+
+    Ok(())
+}
+
+// Credits queued deposits that now fit under the strategy cap; a no-op while the
+// oldest intent still does not fit, so it is safe to crank repeatedly
+pub fn process_deposit_queue(ctx: Context<ProcessDepositQueue>, _strategy_id: Pubkey) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(!ctx.accounts.portfolio.is_paused(current_time), RebalancerError::EmergencyPauseActive);
+    require!(ctx.accounts.strategy.status == StrategyStatus::Active, RebalancerError::StrategyNotFound);
+
+    let queue = &mut ctx.accounts.deposit_queue;
+    let amount = queue.take_fitting(
+        ctx.accounts.strategy.current_balance,
+        ctx.accounts.portfolio.config.strategy_deposit_cap,
+    );
+    if queue.count == 0 {
+        ctx.accounts.strategy.set_deposits_queued(false);
+    }
+
+    if amount == 0 {
+        msg!("No queued deposits fit strategy {} ({} pending)",
+             ctx.accounts.strategy.strategy_id, queue.count);
+        return Ok(());
+    }
+
+    msg!("Processing {} lamports of queued deposits ({} still pending)", amount, queue.count);
+
+    credit_deposit(
+        &mut ctx.accounts.portfolio,
+        &mut ctx.accounts.strategy,
+        ctx.accounts.vault.as_ref(),
+        &ctx.accounts.manager,
+        &ctx.accounts.system_program,
+        amount,
+        current_time,
+    )
+}
+
+// Withdraws a queued intent (nothing was transferred at enqueue, so nothing is refunded)
+pub fn cancel_queued_deposit(
+    ctx: Context<CancelQueuedDeposit>,
+    _strategy_id: Pubkey,
+    index: u8,
+) -> Result<()> {
+    let queue = &mut ctx.accounts.deposit_queue;
+
+    let amount = queue.remove(index)?;
+    if queue.count == 0 {
+        ctx.accounts.strategy.set_deposits_queued(false);
+    }
+
+    msg!("Cancelled queued deposit of {} lamports for strategy {} ({} still pending)",
+         amount, ctx.accounts.strategy.strategy_id, queue.count);
+
+    Ok(())
+}
+
+// Whether a deposit joins the queue rather than being credited. Once intents are
+// pending the queue account is mandatory, so omitting it cannot jump ahead of them.
+pub fn deposit_must_queue(strategy: &Strategy, at_cap: bool, queue: Option<&DepositQueue>) -> Result<bool> {
+    if strategy.deposits_queued() {
+        require!(queue.is_some(), RebalancerError::DepositQueueRequired);
+        return Ok(true);
+    }
+    Ok(at_cap || queue.is_some_and(|q| q.count > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_queue() -> DepositQueue {
+        DepositQueue {
+            portfolio: Pubkey::new_unique(),
+            strategy: Pubkey::new_unique(),
+            count: 0,
+            amounts: [0; 16],
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_deposit_past_cap_is_queued() {
+        let config = PortfolioConfig {
+            strategy_deposit_cap: 1_000_000_000,
+            ..PortfolioConfig::default()
+        };
+        let balance = 900_000_000u64;
+        let mut queue = empty_queue();
+
+        // Within the cap deposits go straight through
+        assert!(!config.exceeds_strategy_cap(balance + 100_000_000));
+        assert!(!PortfolioConfig::default().exceeds_strategy_cap(u64::MAX));
+
+        // Past the cap the intent waits in the queue
+        assert!(config.exceeds_strategy_cap(balance + 200_000_000));
+        queue.enqueue(200_000_000, config.strategy_deposit_cap).unwrap();
+        queue.enqueue(50_000_000, config.strategy_deposit_cap).unwrap();
+        assert_eq!(queue.pending(), &[200_000_000, 50_000_000]);
+
+        // Nothing is released while the strategy is still near the cap
+        assert_eq!(queue.take_fitting(balance, config.strategy_deposit_cap), 0);
+        assert_eq!(queue.count, 2);
+
+        for _ in 2..crate::constants::MAX_QUEUED_DEPOSITS {
+            queue.enqueue(1, config.strategy_deposit_cap).unwrap();
+        }
+        assert!(queue.enqueue(1, config.strategy_deposit_cap).is_err());
+    }
+
+    #[test]
+    fn test_queue_drains_as_capacity_opens() {
+        let cap = 1_000_000_000u64;
+        let mut queue = empty_queue();
+        queue.enqueue(300_000_000, cap).unwrap();
+        queue.enqueue(200_000_000, cap).unwrap();
+        queue.enqueue(100_000_000, cap).unwrap();
+
+        // A withdrawal frees room for the oldest intent only; FIFO order holds
+        // even though the last intent would also fit on its own
+        assert_eq!(queue.take_fitting(650_000_000, cap), 300_000_000);
+        assert_eq!(queue.pending(), &[200_000_000, 100_000_000]);
+
+        // Enough room for everything left
+        assert_eq!(queue.take_fitting(500_000_000, cap), 300_000_000);
+        assert!(queue.pending().is_empty());
+        assert_eq!(queue.amounts, [0; 16]);
+
+        // Raising the cap to uncapped releases any backlog
+        queue.enqueue(u64::MAX / 2, 0).unwrap();
+        assert_eq!(queue.take_fitting(cap, 0), u64::MAX / 2);
+        assert_eq!(queue.count, 0);
+    }
+
+    #[test]
+    fn test_deposit_without_queue_account_cannot_bypass_pending_intents() {
+        let mut strategy = crate::test_fixtures::strategy();
+        let mut queue = empty_queue();
+
+        // Nothing pending: below the cap deposits are credited with or without the queue
+        assert!(!deposit_must_queue(&strategy, false, None).unwrap());
+        assert!(!deposit_must_queue(&strategy, false, Some(&queue)).unwrap());
+        assert!(deposit_must_queue(&strategy, true, Some(&queue)).unwrap());
+
+        // An intent is queued; the strategy records it
+        queue.enqueue(200_000_000, 1_000_000_000).unwrap();
+        strategy.set_deposits_queued(true);
+
+        // Leaving out the queue account no longer credits the deposit ahead of the intent
+        assert_eq!(
+            deposit_must_queue(&strategy, false, None).unwrap_err(),
+            RebalancerError::DepositQueueRequired.into()
+        );
+        assert!(deposit_must_queue(&strategy, false, Some(&queue)).unwrap());
+
+        // Once the queue drains, direct deposits are allowed again
+        assert_eq!(queue.take_fitting(0, 1_000_000_000), 200_000_000);
+        strategy.set_deposits_queued(queue.count > 0);
+        assert!(!deposit_must_queue(&strategy, false, None).unwrap());
+    }
+
+    #[test]
+    fn test_oversized_intent_rejected_and_queued_intents_cancellable() {
+        let cap = 1_000_000_000u64;
+        let mut queue = empty_queue();
+
+        // Larger than the cap on its own: could never be released, so never queued
+        assert_eq!(queue.enqueue(cap + 1, cap).unwrap_err(), RebalancerError::StrategyAtCapacity.into());
+        assert!(queue.pending().is_empty());
+        queue.enqueue(u64::MAX, 0).unwrap(); // Uncapped strategies accept any amount
+        queue.remove(0).unwrap();
+
+        queue.enqueue(900_000_000, cap).unwrap();
+        queue.enqueue(100_000_000, cap).unwrap();
+        queue.enqueue(50_000_000, cap).unwrap();
+
+        // The blocking head intent is cancelled; the rest keep their order and can drain
+        assert_eq!(queue.remove(0).unwrap(), 900_000_000);
+        assert_eq!(queue.pending(), &[100_000_000, 50_000_000]);
+        assert_eq!(queue.take_fitting(850_000_000, cap), 150_000_000);
+
+        assert_eq!(queue.remove(0).unwrap_err(), RebalancerError::QueuedDepositNotFound.into());
+        assert_eq!(queue.amounts, [0; 16]);
+    }
+}
@@ -1,12 +1,13 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::*;
+use super::update_performance::score_strategy;
 
 #[derive(Accounts)]
 pub struct ExecuteRankingCycle<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
         bump = portfolio.bump,
         has_one = manager @ RebalancerError::InvalidManager
     )]
@@ -14,6 +15,9 @@ pub struct ExecuteRankingCycle<'info> {
     
     #[account(mut)]
     pub manager: Signer<'info>,
+    
+    /// Clock sysvar for timestamp operations
+    pub clock: Sysvar<'info, Clock>,
 }
 
 // CONTEXT FOR BATCH STRATEGY RANKING WITH REAL ACCOUNT ITERATION
@@ -21,7 +25,7 @@ pub struct ExecuteRankingCycle<'info> {
 pub struct ExecuteBatchRanking<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
         bump = portfolio.bump,
         has_one = manager @ RebalancerError::InvalidManager
     )]
@@ -58,29 +62,37 @@ pub struct ExecuteBatchRanking<'info> {
     
     #[account(mut)]
     pub manager: Signer<'info>,
+    
+    /// Clock sysvar for timestamp operations
+    pub clock: Sysvar<'info, Clock>,
 }
 
 pub fn execute_ranking_cycle(
     ctx: Context<ExecuteRankingCycle>,
 ) -> Result<()> {
+    let current_time = ctx.accounts.clock.unix_timestamp;
     let portfolio = &mut ctx.accounts.portfolio;
-    let current_time = Clock::get()?.unix_timestamp;
     
-    // REBALANCING ELIGIBILITY CHECKS
-    require!(!portfolio.emergency_pause, RebalancerError::EmergencyPauseActive);
-    require!(
-        portfolio.can_rebalance(current_time),
-        RebalancerError::RebalanceIntervalNotMet
-    );
-    require!(portfolio.total_strategies >= 2, RebalancerError::InsufficientStrategies);
+    // REBALANCING ELIGIBILITY CHECKS + CYCLE BOOKKEEPING
+    if !portfolio.record_ranking_cycle(current_time)? {
+        msg!("Ranking cycle #{} already open; awaiting execute_batch_ranking",
+             portfolio.ranking_cycle_count);
+        return Ok(());
+    }
     
-    msg!("Ranking cycle initiated for {} strategies", portfolio.total_strategies);
+    msg!("Ranking cycle #{} initiated for {} strategies",
+         portfolio.ranking_cycle_count, portfolio.total_strategies);
     
     // REAL IMPLEMENTATION: This initiates the ranking process
     // In practice, this would trigger multiple ExecuteBatchRanking calls
     // with batches of strategy account references due to Solana's account limits
     
-    portfolio.last_rebalance = current_time;
+    emit!(RankingCycleEvent {
+        portfolio: portfolio.key(),
+        ranking_cycle_count: portfolio.ranking_cycle_count,
+        total_strategies: portfolio.total_strategies,
+        timestamp: current_time,
+    });
     
     msg!("Ranking cycle completed. Use execute_batch_ranking for actual strategy processing.");
     
@@ -91,116 +103,174 @@ pub fn execute_ranking_cycle(
 pub fn execute_batch_ranking(
     ctx: Context<ExecuteBatchRanking>,
 ) -> Result<()> {
-    // Note: We still get the fixed threshold from portfolio for backwards compatibility
-    // but will calculate a dynamic threshold based on volatility
-    let _portfolio_fixed_threshold = ctx.accounts.portfolio.rebalance_threshold;
-    
-    // Create StrategyData from accounts without borrowing references
-    let mut strategy_data = Vec::new();
-    
-    // Add strategy_1 if active
-    if ctx.accounts.strategy_1.status == StrategyStatus::Active {
-        strategy_data.push(StrategyData::from_strategy(
-            &ctx.accounts.strategy_1, 
-            25 // Temporary value, will be updated by calculate_percentile_rankings
-        ));
-    }
-    
-    // Add strategy_2 if active
-    if ctx.accounts.strategy_2.status == StrategyStatus::Active {
-        strategy_data.push(StrategyData::from_strategy(
-            &ctx.accounts.strategy_2, 
-            25 // Temporary value, will be updated by calculate_percentile_rankings
-        ));
-    }
-    
-    // Add strategy_3 if present and active
-    if let Some(ref strategy_3) = ctx.accounts.strategy_3 {
-        if strategy_3.status == StrategyStatus::Active {
-            strategy_data.push(StrategyData::from_strategy(
-                strategy_3, 
-                25 // Temporary value, will be updated by calculate_percentile_rankings
-            ));
-        }
+    crate::profile_cu!("execute_batch_ranking start");
+    
+    let current_time = ctx.accounts.clock.unix_timestamp;
+    let accounts = &mut *ctx.accounts;
+    
+    let mut slots: Vec<&mut Strategy> = vec![&mut *accounts.strategy_1, &mut *accounts.strategy_2];
+    if let Some(strategy_3) = accounts.strategy_3.as_mut() {
+        slots.push(&mut **strategy_3);
+    }
+    if let Some(strategy_4) = accounts.strategy_4.as_mut() {
+        slots.push(&mut **strategy_4);
     }
     
-    // Add strategy_4 if present and active
-    if let Some(ref strategy_4) = ctx.accounts.strategy_4 {
-        if strategy_4.status == StrategyStatus::Active {
-            strategy_data.push(StrategyData::from_strategy(
-                strategy_4, 
-                25 // Temporary value, will be updated by calculate_percentile_rankings
-            ));
-        }
+    let outcome = rank_strategy_batch(&mut accounts.portfolio, &mut slots, current_time)?;
+    
+    for underperformer in &outcome.underperformers {
+        msg!("Underperformer identified: {}", underperformer);
     }
     
-    require!(!strategy_data.is_empty(), RebalancerError::InsufficientStrategies);
-    require!(strategy_data.len() >= 2, RebalancerError::InsufficientStrategies);
+    for candidate in &outcome.rebalancing_candidates {
+        msg!("Rebalancing candidate: {}", candidate);
+    }
     
-    // Execute the core ranking algorithm (which now calculates dynamic threshold internally)
-    let underperformers = calculate_percentile_rankings(&mut strategy_data)?;
+    crate::profile_cu!("execute_batch_ranking end");
     
-    // Get the dynamic threshold that was calculated
-    let dynamic_threshold = if !strategy_data.is_empty() {
-        strategy_data[0].rebalance_threshold
-    } else {
-        25u8 // Fallback
-    };
+    Ok(())
+}
+
+// Result of ranking one batch of strategy slots
+#[derive(Debug, Clone)]
+pub struct BatchRankingOutcome {
+    pub strategies_ranked: usize,
+    pub underperformers: Vec<Pubkey>,
+    pub rebalancing_candidates: Vec<Pubkey>,
+    pub auto_paused: Vec<Pubkey>,
+    pub needs_rescore: Vec<Pubkey>,
+    pub dynamic_threshold: u8,
+}
+
+// Core of execute_batch_ranking with the timestamp supplied by the caller: ranks the
+// active slots, writes ranks and timestamps back, and closes the portfolio's cycle.
+// Runs under the portfolio's ranking lock, released whether or not ranking succeeds.
+pub fn rank_strategy_batch(
+    portfolio: &mut Portfolio,
+    slots: &mut [&mut Strategy],
+    current_time: i64,
+) -> Result<BatchRankingOutcome> {
+    portfolio.with_ranking_lock(|portfolio| rank_locked_batch(portfolio, slots, current_time))
+}
+
+fn rank_locked_batch(
+    portfolio: &mut Portfolio,
+    slots: &mut [&mut Strategy],
+    current_time: i64,
+) -> Result<BatchRankingOutcome> {
+    let config = portfolio.config;
+    let scoring_epoch = portfolio.scoring_epoch;
     
-    // Now update the strategy accounts with new percentile ranks
-    let current_time = Clock::get()?.unix_timestamp;
+    // DUPLICATE-SLOT GUARD: one strategy may occupy at most one slot per batch
+    let slot_ids: Vec<Pubkey> = slots.iter().map(|s| s.strategy_id).collect();
+    ensure_unique_strategy_ids(&slot_ids)?;
     
-    // Update each strategy account individually based on strategy_data results
-    for data in &strategy_data {
-        if ctx.accounts.strategy_1.strategy_id == data.strategy_id {
-            ctx.accounts.strategy_1.percentile_rank = data.percentile_rank;
-            ctx.accounts.strategy_1.last_updated = current_time;
-            msg!("Updated strategy {} rank to {}%", data.strategy_id, data.percentile_rank);
-        }
-        
-        if ctx.accounts.strategy_2.strategy_id == data.strategy_id {
-            ctx.accounts.strategy_2.percentile_rank = data.percentile_rank;
-            ctx.accounts.strategy_2.last_updated = current_time;
-            msg!("Updated strategy {} rank to {}%", data.strategy_id, data.percentile_rank);
-        }
-        
-        if let Some(ref mut strategy_3) = ctx.accounts.strategy_3 {
-            if strategy_3.strategy_id == data.strategy_id {
-                strategy_3.percentile_rank = data.percentile_rank;
-                strategy_3.last_updated = current_time;
-                msg!("Updated strategy {} rank to {}%", data.strategy_id, data.percentile_rank);
-            }
-        }
-        
-        if let Some(ref mut strategy_4) = ctx.accounts.strategy_4 {
-            if strategy_4.strategy_id == data.strategy_id {
-                strategy_4.percentile_rank = data.percentile_rank;
-                strategy_4.last_updated = current_time;
-                msg!("Updated strategy {} rank to {}%", data.strategy_id, data.percentile_rank);
+    // SCORE PERSISTENCE GUARD: stored scores must still match the stored metrics
+    if config.score_check != ScoreCheckMode::Off {
+        for strategy in slots.iter() {
+            if let Some(expected_score) = detect_score_mismatch(strategy, &config)? {
+                msg!("Stale score: strategy={}, stored={}, expected={}",
+                     strategy.strategy_id, strategy.performance_score, expected_score);
+                emit!(ScoreMismatchEvent {
+                    strategy_id: strategy.strategy_id,
+                    stored_score: strategy.performance_score,
+                    expected_score,
+                });
+                require!(config.score_check != ScoreCheckMode::Strict, RebalancerError::ScoreMismatch);
             }
         }
     }
     
-    // Calculate rebalancing candidates using dynamic threshold
-    let mut rebalancing_candidates = Vec::new();
+    // STALENESS GUARD: active strategies with outdated metrics are paused until refreshed
+    let mut auto_paused = Vec::new();
+    for strategy in slots.iter_mut() {
+        if strategy.status == StrategyStatus::Active
+            && strategy.metrics_stale(current_time, config.max_metric_staleness)
+        {
+            strategy.transition_status(StrategyStatus::Paused)?;
+            strategy.set_auto_paused(true); // Lifted by the next performance update
+            msg!("Strategy {} auto-paused: metrics last updated at {}", strategy.strategy_id, strategy.last_performance_update);
+            emit!(StrategyAutoPausedEvent {
+                strategy_id: strategy.strategy_id,
+                last_performance_update: strategy.last_performance_update,
+                max_metric_staleness: config.max_metric_staleness,
+                timestamp: current_time,
+            });
+            auto_paused.push(strategy.strategy_id);
+        }
+    }
     
-    if should_rebalance_strategy(&ctx.accounts.strategy_1, dynamic_threshold) {
-        rebalancing_candidates.push(ctx.accounts.strategy_1.strategy_id);
+    // SCORING EPOCH GUARD: scores computed before the last formula change are flagged,
+    // and left out of ranking under StaleEpochPolicy::Exclude
+    let needs_rescore: Vec<Pubkey> = slots
+        .iter()
+        .filter(|s| s.status == StrategyStatus::Active && s.needs_rescore(scoring_epoch))
+        .map(|s| s.strategy_id)
+        .collect();
+    for strategy_id in &needs_rescore {
+        msg!("Strategy {} needs re-scoring: scored before scoring epoch {}", strategy_id, scoring_epoch);
     }
+    let excluded = |s: &Strategy| {
+        config.stale_epoch_policy == StaleEpochPolicy::Exclude && s.needs_rescore(scoring_epoch)
+    };
+    
+    // Only active strategies are ranked
+    let mut strategy_data: Vec<StrategyData> = slots
+        .iter()
+        .filter(|s| s.status == StrategyStatus::Active && !excluded(s))
+        .map(|s| StrategyData::from_strategy_with_config(
+            s,
+            25, // Temporary value, will be updated by calculate_percentile_rankings
+            &config,
+        ))
+        .collect();
+    canonicalize_strategy_order(&mut strategy_data);
     
-    if should_rebalance_strategy(&ctx.accounts.strategy_2, dynamic_threshold) {
-        rebalancing_candidates.push(ctx.accounts.strategy_2.strategy_id);
+    // Keep the auto-pauses and re-score flags even when too few strategies are left to rank
+    if strategy_data.len() < 2 && (!auto_paused.is_empty() || !needs_rescore.is_empty()) {
+        msg!("Batch ranking skipped: {} strategies auto-paused, {} awaiting re-score, {} left to rank",
+             auto_paused.len(), needs_rescore.len(), strategy_data.len());
+        return Ok(BatchRankingOutcome {
+            strategies_ranked: 0,
+            underperformers: Vec::new(),
+            rebalancing_candidates: Vec::new(),
+            auto_paused,
+            needs_rescore,
+            dynamic_threshold: portfolio.last_dynamic_threshold(),
+        });
     }
+    require!(strategy_data.len() >= 2, RebalancerError::InsufficientStrategies);
     
-    if let Some(ref strategy_3) = ctx.accounts.strategy_3 {
-        if should_rebalance_strategy(strategy_3, dynamic_threshold) {
-            rebalancing_candidates.push(strategy_3.strategy_id);
+    // Execute the core ranking algorithm (which now calculates dynamic threshold internally)
+    let underperformers = if strategy_data.len() == 2 {
+        rank_two_strategies(&mut strategy_data, &config)?
+    } else {
+        calculate_percentile_rankings_with_config(&mut strategy_data, &config)?
+    };
+    
+    // Every ranked strategy carries the dynamic threshold that was calculated
+    let dynamic_threshold = strategy_data[0].rebalance_threshold;
+    
+    portfolio.set_last_dynamic_threshold(dynamic_threshold);
+    portfolio.set_last_ranked_at(current_time);
+    portfolio.complete_ranking_cycle(current_time);
+    
+    // Update each strategy account based on strategy_data results
+    for data in &strategy_data {
+        if let Some(strategy) = slots.iter_mut().find(|s| s.strategy_id == data.strategy_id) {
+            strategy.percentile_rank = data.percentile_rank;
+            strategy.last_updated = current_time;
+            strategy.set_rank_stale(false);
+            strategy.track_low_rank(config.deprecation_rank_cutoff);
+            msg!("Updated strategy {} rank to {}%", data.strategy_id, data.percentile_rank);
         }
     }
     
-    if let Some(ref strategy_4) = ctx.accounts.strategy_4 {
-        if should_rebalance_strategy(strategy_4, dynamic_threshold) {
-            rebalancing_candidates.push(strategy_4.strategy_id);
+    // Calculate rebalancing candidates using dynamic threshold
+    let mut rebalancing_candidates = Vec::new();
+    for strategy in slots.iter_mut() {
+        if !excluded(strategy) && should_rebalance_with_config(strategy, dynamic_threshold, &config, current_time) {
+            rebalancing_candidates.push(strategy.strategy_id);
         }
     }
     
@@ -211,15 +281,14 @@ pub fn execute_batch_ranking(
          rebalancing_candidates.len(),
          dynamic_threshold);
     
-    for underperformer in &underperformers {
-        msg!("Underperformer identified: {}", underperformer);
-    }
-    
-    for candidate in &rebalancing_candidates {
-        msg!("Rebalancing candidate: {}", candidate);
-    }
-    
-    Ok(())
+    Ok(BatchRankingOutcome {
+        strategies_ranked: strategy_data.len(),
+        underperformers,
+        rebalancing_candidates,
+        auto_paused,
+        needs_rescore,
+        dynamic_threshold,
+    })
 }
 
 // COMPREHENSIVE STRATEGY ITERATION WITH ACCOUNT LOADING
@@ -255,6 +324,7 @@ pub fn process_all_strategies_with_ranking(
             if let Some(data) = strategy_data.iter().find(|d| d.strategy_id == strategy.strategy_id) {
                 strategy.percentile_rank = data.percentile_rank;
                 strategy.last_updated = Clock::get()?.unix_timestamp;
+                strategy.set_rank_stale(false);
             }
         }
     }
@@ -296,10 +366,12 @@ pub fn calculate_average_volatility(strategies: &[StrategyData]) -> Result<u32>
     let mut count = 0u64;
 
     for strategy in strategies {
-        // Convert volatility_score (0-10000 representing 0-100%) to percentage
-        let volatility_pct = strategy.volatility_score
-            .checked_div(100)
-            .ok_or(RebalancerError::DivisionByZero)?;
+        // Corrupt scores above the 0-10000 range degrade to the maximum rather than
+        // skewing the average (logged instead of asserted so ranking still completes)
+        if strategy.volatility_score > 10000 {
+            msg!(&out_of_range_log("volatility score", strategy.volatility_score, "0-10000"));
+        }
+        let volatility_pct = VolatilityScore::saturating_from_bps(strategy.volatility_score).percent();
         
         total_volatility = total_volatility
             .checked_add(volatility_pct as u64)
@@ -328,8 +400,17 @@ pub fn calculate_average_volatility(strategies: &[StrategyData]) -> Result<u32>
 /// Calculate dynamic threshold based on average volatility
 /// Formula: Dynamic Threshold = Base Threshold + Volatility Adjustment
 /// Where: Base = 15%, Volatility Adjustment = (Avg Volatility / 100) × 20%
-/// Range: 10% minimum, 40% maximum
+/// Range: 10% minimum, 40% maximum (default portfolio config)
 pub fn calculate_dynamic_threshold(strategies: &[StrategyData]) -> Result<u8> {
+    calculate_dynamic_threshold_with_config(strategies, &PortfolioConfig::default())
+}
+
+/// Same formula as `calculate_dynamic_threshold`, bounded by the portfolio's
+/// configured `threshold_floor` / `threshold_ceiling` instead of 10% / 40%
+pub fn calculate_dynamic_threshold_with_config(
+    strategies: &[StrategyData],
+    config: &PortfolioConfig,
+) -> Result<u8> {
     if strategies.is_empty() {
         return Err(RebalancerError::InsufficientStrategies.into());
     }
@@ -352,11 +433,13 @@ pub fn calculate_dynamic_threshold(strategies: &[StrategyData]) -> Result<u8> {
         .checked_add(volatility_adjustment)
         .ok_or(RebalancerError::MathOverflow)?;
     
-    // Enforce bounds: 10% minimum, 40% maximum
-    let bounded_threshold = if dynamic_threshold < 10 {
-        10u8
-    } else if dynamic_threshold > 40 {
-        40u8
+    // Enforce configured bounds (default: 10% minimum, 40% maximum)
+    let floor = config.threshold_floor as u32;
+    let ceiling = config.threshold_ceiling as u32;
+    let bounded_threshold = if dynamic_threshold < floor {
+        floor as u8
+    } else if dynamic_threshold > ceiling {
+        ceiling as u8
     } else {
         dynamic_threshold as u8
     };
@@ -368,17 +451,28 @@ pub fn calculate_dynamic_threshold(strategies: &[StrategyData]) -> Result<u8> {
 }
 
 pub fn calculate_percentile_rankings(strategies: &mut Vec<StrategyData>) -> Result<Vec<Pubkey>> {
+    calculate_percentile_rankings_with_config(strategies, &PortfolioConfig::default())
+}
+
+pub fn calculate_percentile_rankings_with_config(
+    strategies: &mut [StrategyData],
+    config: &PortfolioConfig,
+) -> Result<Vec<Pubkey>> {
+    crate::profile_cu!("calculate_percentile_rankings start");
+    
     require!(!strategies.is_empty(), RebalancerError::InsufficientStrategies);
     
+    // Refuse to rank on tiebreakers alone before any update_performance has run
+    require!(
+        strategies.iter().any(|s| s.performance_score > 0),
+        RebalancerError::NoPerformanceData
+    );
+    
     // Calculate dynamic threshold based on volatility
-    let dynamic_threshold = calculate_dynamic_threshold(strategies)?;
+    let dynamic_threshold = calculate_dynamic_threshold_with_config(strategies, config)?;
     
     // SORT STRATEGIES BY PERFORMANCE SCORE (DESCENDING - HIGHEST FIRST)
-    strategies.sort_by(|a, b| {
-        b.performance_score.cmp(&a.performance_score)
-            .then(b.current_balance.cmp(&a.current_balance)) // Tiebreaker: higher balance wins
-            .then(a.volatility_score.cmp(&b.volatility_score)) // Secondary tiebreaker: lower volatility wins
-    });
+    strategies.sort_by(|a, b| compare_ranking_order_with(a, b, config.tie_break));
     
     let total_strategies = strategies.len();
     let mut underperformers = Vec::new();
@@ -422,9 +516,110 @@ pub fn calculate_percentile_rankings(strategies: &mut Vec<StrategyData>) -> Resu
              dynamic_threshold);
     }
     
+    // CANONICAL OUTPUT ORDER: sort by pubkey bytes so callers get the same list
+    // regardless of the order strategy accounts were supplied in
+    underperformers.sort();
+    
+    crate::profile_cu!("calculate_percentile_rankings end");
+    
+    Ok(underperformers)
+}
+
+// Ranking order: best first
+pub fn compare_ranking_order(a: &StrategyData, b: &StrategyData) -> std::cmp::Ordering {
+    compare_ranking_order_with(a, b, TieBreak::BalanceFirst)
+}
+
+pub fn compare_ranking_order_with(
+    a: &StrategyData,
+    b: &StrategyData,
+    tie_break: TieBreak,
+) -> std::cmp::Ordering {
+    let by_balance = b.current_balance.cmp(&a.current_balance);      // Higher balance wins
+    let by_volatility = a.volatility_score.cmp(&b.volatility_score); // Lower volatility wins
+    
+    let tiebreakers = match tie_break {
+        TieBreak::BalanceFirst => by_balance.then(by_volatility),
+        TieBreak::VolatilityFirst => by_volatility.then(by_balance),
+    };
+    // Strategy id is the ultimate key so fully tied strategies always land in the
+    // same order regardless of the order accounts were passed in
+    b.performance_score.cmp(&a.performance_score)
+        .then(tiebreakers)
+        .then(a.strategy_id.cmp(&b.strategy_id))
+}
+
+// CANONICAL INPUT ORDER
+// Strategies collected from caller-ordered accounts are sorted by id before ranking,
+// so no step downstream can depend on the order the accounts were passed in
+pub fn canonicalize_strategy_order(strategies: &mut [StrategyData]) {
+    strategies.sort_by_key(|s| s.strategy_id);
+}
+
+// TWO-STRATEGY FAST PATH
+// Same result as calculate_percentile_rankings_with_config for exactly two strategies
+// (ranks 100/0, lower one flagged when under the dynamic threshold) without the
+// sort and percentile loop
+pub fn rank_two_strategies(
+    strategies: &mut [StrategyData],
+    config: &PortfolioConfig,
+) -> Result<Vec<Pubkey>> {
+    require!(strategies.len() == 2, RebalancerError::InsufficientStrategies);
+    require!(
+        strategies.iter().any(|s| s.performance_score > 0),
+        RebalancerError::NoPerformanceData
+    );
+    
+    let dynamic_threshold = calculate_dynamic_threshold_with_config(strategies, config)?;
+    
+    if compare_ranking_order_with(&strategies[0], &strategies[1], config.tie_break) == std::cmp::Ordering::Greater {
+        strategies.swap(0, 1);
+    }
+    
+    strategies[0].percentile_rank = 100;
+    strategies[1].percentile_rank = 0;
+    for strategy_data in strategies.iter_mut() {
+        strategy_data.rebalance_threshold = dynamic_threshold;
+    }
+    
+    let mut underperformers = Vec::new();
+    for strategy_data in strategies.iter() {
+        if strategy_data.percentile_rank < dynamic_threshold {
+            underperformers.push(strategy_data.strategy_id);
+        }
+    }
+    underperformers.sort();
+    
     Ok(underperformers)
 }
 
+#[event]
+pub struct RankingCycleEvent {
+    pub portfolio: Pubkey,
+    pub ranking_cycle_count: u64,
+    pub total_strategies: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyAutoPausedEvent {
+    pub strategy_id: Pubkey,
+    pub last_performance_update: i64,
+    pub max_metric_staleness: u32,
+    pub timestamp: i64,
+}
+
+// Rejects a batch in which the same strategy fills more than one slot
+pub fn ensure_unique_strategy_ids(strategy_ids: &[Pubkey]) -> Result<()> {
+    for (index, strategy_id) in strategy_ids.iter().enumerate() {
+        require!(
+            !strategy_ids[index + 1..].contains(strategy_id),
+            RebalancerError::DuplicateStrategy
+        );
+    }
+    Ok(())
+}
+
 // HELPER STRUCTURE FOR RANKING CALCULATIONS
 #[derive(Debug, Clone)]
 pub struct StrategyData {
@@ -457,19 +652,45 @@ impl StrategyData {
             rebalance_threshold,
         }
     }
+    
+    // Ranks on the smoothed score when the portfolio enables smoothing
+    pub fn from_strategy_with_config(
+        strategy: &Strategy,
+        rebalance_threshold: u8,
+        config: &PortfolioConfig,
+    ) -> Self {
+        StrategyData {
+            performance_score: strategy.ranking_score(config.smooth_scores),
+            ..StrategyData::from_strategy(strategy, rebalance_threshold)
+        }
+    }
 }
 
 // REBALANCING TRIGGER LOGIC
 pub fn should_rebalance_strategy(
     strategy: &Strategy,
     portfolio_threshold: u8,
+) -> bool {
+    should_rebalance_strategy_with(strategy, portfolio_threshold, false)
+}
+
+// Same trigger with paused strategies optionally eligible (extraction only)
+pub fn should_rebalance_strategy_with(
+    strategy: &Strategy,
+    portfolio_threshold: u8,
+    drain_paused: bool,
 ) -> bool {
     // Strategy qualifies for rebalancing if:
     // 1. It's in the bottom percentile based on portfolio threshold
     // 2. It has sufficient balance to make rebalancing worthwhile
-    // 3. It's currently active
+    // 3. It's currently active (or paused, when draining paused strategies)
+    // Pinned strategies are never rebalanced, whatever their rank
     
-    if strategy.status != StrategyStatus::Active {
+    if strategy.pinned {
+        return false;
+    }
+    
+    if !strategy.extraction_eligible(drain_paused) {
         return false;
     }
     
@@ -481,10 +702,75 @@ pub fn should_rebalance_strategy(
     strategy.percentile_rank < portfolio_threshold
 }
 
+// CONFIG-AWARE REBALANCING TRIGGER
+// Strategies inside their warmup window are protected regardless of rank; the
+// rest go through the hysteresis band
+pub fn should_rebalance_with_config(
+    strategy: &mut Strategy,
+    portfolio_threshold: u8,
+    config: &PortfolioConfig,
+    current_time: i64,
+) -> bool {
+    if strategy.in_warmup(current_time, config.warmup_window) {
+        return false;
+    }
+    
+    // Paused strategies keep their last rank, so the hysteresis band has nothing to settle
+    if strategy.status == StrategyStatus::Paused {
+        return should_rebalance_strategy_with(strategy, portfolio_threshold, config.drain_paused);
+    }
+    
+    should_rebalance_with_hysteresis(strategy, portfolio_threshold, config.rank_hysteresis)
+}
+
+// Recomputes the score from the strategy's stored metrics; returns the expected
+// score when the stored one has drifted
+pub fn detect_score_mismatch(strategy: &Strategy, config: &PortfolioConfig) -> Result<Option<u64>> {
+    let expected_score = score_strategy(strategy, config)?;
+    
+    Ok((expected_score != strategy.performance_score).then_some(expected_score))
+}
+
+#[event]
+pub struct ScoreMismatchEvent {
+    pub strategy_id: Pubkey,
+    pub stored_score: u64,
+    pub expected_score: u64,
+}
+
+// HYSTERESIS-AWARE REBALANCING TRIGGER
+// With a band configured, a strategy must rank below threshold - hysteresis to be
+// rebalanced, and once rebalanced stays ineligible until it ranks above
+// threshold + hysteresis. The band is entered by extract_from_protocol when
+// capital is actually pulled, not by being flagged here.
+pub fn should_rebalance_with_hysteresis(
+    strategy: &mut Strategy,
+    portfolio_threshold: u8,
+    hysteresis: u8,
+) -> bool {
+    if hysteresis == 0 {
+        return should_rebalance_strategy(strategy, portfolio_threshold);
+    }
+    
+    if strategy.awaiting_recovery
+        && strategy.percentile_rank > portfolio_threshold.saturating_add(hysteresis)
+    {
+        strategy.awaiting_recovery = false;
+    }
+    
+    if strategy.awaiting_recovery {
+        return false;
+    }
+    
+    should_rebalance_strategy(strategy, portfolio_threshold.saturating_sub(hysteresis))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use anchor_lang::prelude::Pubkey;
+    use crate::instructions::update_performance::calculate_performance_score;
+    use crate::test_fixtures;
     
     #[test]
     fn test_calculate_average_volatility() {
@@ -595,6 +881,41 @@ mod tests {
         assert!(threshold >= 10);
     }
     
+    #[test]
+    fn test_configured_threshold_floor() {
+        let low_vol_strategies = vec![
+            StrategyData {
+                strategy_id: Pubkey::new_unique(),
+                performance_score: 8000,
+                current_balance: 1_000_000_000,
+                volatility_score: 1000, // 10% volatility
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            },
+        ];
+        
+        // Formula output: 15% + (10/100 * 20%) = 17%
+        assert_eq!(calculate_dynamic_threshold(&low_vol_strategies).unwrap(), 17);
+        
+        // Conservative portfolio raises the floor to 20%
+        let config = PortfolioConfig {
+            threshold_floor: 20,
+            threshold_ceiling: 40,
+            ..PortfolioConfig::default()
+        };
+        let threshold = calculate_dynamic_threshold_with_config(&low_vol_strategies, &config).unwrap();
+        assert_eq!(threshold, 20);
+        
+        // Ceiling caps high-volatility output
+        let capped = PortfolioConfig {
+            threshold_floor: 10,
+            threshold_ceiling: 12,
+            ..PortfolioConfig::default()
+        };
+        let threshold = calculate_dynamic_threshold_with_config(&low_vol_strategies, &capped).unwrap();
+        assert_eq!(threshold, 12);
+    }
+    
     #[test] 
     fn test_percentile_ranking_with_dynamic_threshold() {
         let mut strategies = vec![
@@ -669,6 +990,46 @@ mod tests {
         assert_eq!(strategies[0].current_balance, 2_000_000_000);
     }
     
+    #[test]
+    fn test_volatility_first_tie_break() {
+        let large_risky = StrategyData {
+            strategy_id: Pubkey::new_unique(),
+            performance_score: 5000,
+            current_balance: 2_000_000_000,
+            volatility_score: 6000,
+            percentile_rank: 0,
+            rebalance_threshold: 25,
+        };
+        let small_safe = StrategyData {
+            strategy_id: Pubkey::new_unique(),
+            performance_score: 5000,
+            current_balance: 1_000_000_000,
+            volatility_score: 1000,
+            percentile_rank: 0,
+            rebalance_threshold: 25,
+        };
+        
+        // Default: higher balance wins the tie
+        let mut strategies = vec![small_safe.clone(), large_risky.clone()];
+        calculate_percentile_rankings_with_config(&mut strategies, &PortfolioConfig::default()).unwrap();
+        assert_eq!(strategies[0].strategy_id, large_risky.strategy_id);
+        
+        // Volatility-first: the less risky strategy wins despite the lower balance
+        let config = PortfolioConfig {
+            tie_break: TieBreak::VolatilityFirst,
+            ..PortfolioConfig::default()
+        };
+        let mut strategies = vec![large_risky.clone(), small_safe.clone()];
+        calculate_percentile_rankings_with_config(&mut strategies, &config).unwrap();
+        assert_eq!(strategies[0].strategy_id, small_safe.strategy_id);
+        assert_eq!(strategies[0].percentile_rank, 100);
+        
+        // Fast path honours the same preference
+        let mut strategies = vec![large_risky.clone(), small_safe.clone()];
+        rank_two_strategies(&mut strategies, &config).unwrap();
+        assert_eq!(strategies[0].strategy_id, small_safe.strategy_id);
+    }
+    
     #[test]
     fn test_edge_cases() {
         // Single strategy
@@ -758,87 +1119,46 @@ mod tests {
     #[test]
     fn test_should_rebalance_strategy_logic() {
         let good_strategy = Strategy {
-            strategy_id: Pubkey::new_unique(),
-            protocol_type: ProtocolType::StableLending {
-                pool_id: Pubkey::new_unique(),
-                utilization: 8000,
-                reserve_address: Pubkey::new_unique(),
-            },
             current_balance: 1_000_000_000, // 1 SOL
             yield_rate: 8000,
             volatility_score: 2000,
             performance_score: 7500,
+            smoothed_score: 7500,
             percentile_rank: 75, // Good rank
-            last_updated: 0,
-            status: StrategyStatus::Active,
-            total_deposits: 1_000_000_000,
-            total_withdrawals: 0,
-            creation_time: 0,
-            bump: 255,
-            reserved: [0; 23],
+            ..test_fixtures::strategy()
         };
         
         let poor_strategy = Strategy {
-            strategy_id: Pubkey::new_unique(),
-            protocol_type: ProtocolType::StableLending {
-                pool_id: Pubkey::new_unique(),
-                utilization: 8000,
-                reserve_address: Pubkey::new_unique(),
-            },
             current_balance: 100_000_000, // 0.1 SOL
             yield_rate: 2000,
             volatility_score: 8000,
             performance_score: 2000,
+            smoothed_score: 2000,
             percentile_rank: 10, // Poor rank
-            last_updated: 0,
-            status: StrategyStatus::Active,
             total_deposits: 100_000_000,
-            total_withdrawals: 0,
-            creation_time: 0,
-            bump: 255,
-            reserved: [0; 23],
+            ..test_fixtures::strategy()
         };
         
         let inactive_strategy = Strategy {
-            strategy_id: Pubkey::new_unique(),
-            protocol_type: ProtocolType::StableLending {
-                pool_id: Pubkey::new_unique(),
-                utilization: 8000,
-                reserve_address: Pubkey::new_unique(),
-            },
             current_balance: 1_000_000_000,
             yield_rate: 1000,
             volatility_score: 9000,
             performance_score: 1000,
+            smoothed_score: 1000,
             percentile_rank: 5,
-            last_updated: 0,
             status: StrategyStatus::Paused, // Not active
-            total_deposits: 1_000_000_000,
-            total_withdrawals: 0,
-            creation_time: 0,
-            bump: 255,
-            reserved: [0; 23],
+            ..test_fixtures::strategy()
         };
         
         let dust_strategy = Strategy {
-            strategy_id: Pubkey::new_unique(),
-            protocol_type: ProtocolType::StableLending {
-                pool_id: Pubkey::new_unique(),
-                utilization: 8000,
-                reserve_address: Pubkey::new_unique(),
-            },
             current_balance: 10_000_000, // 0.01 SOL - below threshold
             yield_rate: 1000,
             volatility_score: 9000,
             performance_score: 1000,
+            smoothed_score: 1000,
             percentile_rank: 5,
-            last_updated: 0,
-            status: StrategyStatus::Active,
             total_deposits: 10_000_000,
-            total_withdrawals: 0,
-            creation_time: 0,
-            bump: 255,
-            reserved: [0; 23],
+            ..test_fixtures::strategy()
         };
         
         // Test rebalancing logic with various dynamic thresholds
@@ -850,6 +1170,289 @@ mod tests {
         // Test with different dynamic thresholds
         assert!(!should_rebalance_strategy(&poor_strategy, 5)); // With 5% threshold, rank 10 is safe
         assert!(should_rebalance_strategy(&poor_strategy, 15)); // With 15% threshold, rank 10 should rebalance
+        
+        // Pinned strategies are excluded regardless of rank
+        let pinned_strategy = Strategy { pinned: true, ..poor_strategy };
+        assert!(!should_rebalance_strategy(&pinned_strategy, 25));
+        assert!(!should_rebalance_strategy(&pinned_strategy, 50));
+    }
+    
+    #[test]
+    fn test_hysteresis_prevents_churn_around_threshold() {
+        let mut strategy = Strategy {
+            current_balance: 1_000_000_000,
+            yield_rate: 3000,
+            volatility_score: 4000,
+            performance_score: 3000,
+            smoothed_score: 3000,
+            percentile_rank: 0,
+            ..test_fixtures::strategy()
+        };
+        
+        // Rank oscillates around a 25% threshold
+        let ranks = [19u8, 24, 26, 19, 24, 31, 19];
+        
+        let mut plain = strategy.clone();
+        let plain_rebalances = ranks
+            .iter()
+            .filter(|&&rank| {
+                plain.percentile_rank = rank;
+                should_rebalance_with_hysteresis(&mut plain, 25, 0)
+            })
+            .count();
+        assert_eq!(plain_rebalances, 5);
+        
+        // 5-point band: rebalanced at 19, held until rank exceeds 30, then eligible again
+        let banded: Vec<bool> = ranks
+            .iter()
+            .map(|&rank| {
+                strategy.percentile_rank = rank;
+                let candidate = should_rebalance_with_hysteresis(&mut strategy, 25, 5);
+                strategy.awaiting_recovery |= candidate; // As extract_from_protocol would
+                candidate
+            })
+            .collect();
+        assert_eq!(banded, vec![true, false, false, false, false, false, true]);
+    }
+    
+    #[test]
+    fn test_stale_stored_score_detected() {
+        let config = PortfolioConfig::default();
+        let mut strategy = Strategy {
+            current_balance: 5_000_000_000,
+            yield_rate: 1200,
+            volatility_score: 2000,
+            total_deposits: 5_000_000_000,
+            high_water_mark: 5_000_000_000,
+            ..test_fixtures::strategy()
+        };
+        strategy.performance_score = calculate_performance_score(1200, 5_000_000_000, 2000).unwrap();
+        assert_eq!(detect_score_mismatch(&strategy, &config).unwrap(), None);
+        
+        // Metrics changed without a score recompute
+        strategy.yield_rate = 300;
+        let expected = calculate_performance_score(300, 5_000_000_000, 2000).unwrap();
+        assert_eq!(detect_score_mismatch(&strategy, &config).unwrap(), Some(expected));
+    }
+    
+    #[test]
+    fn test_warmup_protects_new_strategies() {
+        let registered_at = 1_000_000i64;
+        let mut strategy = Strategy {
+            current_balance: 1_000_000_000,
+            volatility_score: 1500,
+            performance_score: 500,
+            smoothed_score: 500,
+            percentile_rank: 5, // Ranked at the bottom right after registration
+            last_updated: registered_at,
+            creation_time: registered_at,
+            high_water_mark: 1_000_000_000,
+            ..test_fixtures::strategy()
+        };
+        let config = PortfolioConfig {
+            warmup_window: 7 * 86400,
+            ..PortfolioConfig::default()
+        };
+        
+        // Protected for the whole week despite the low rank
+        assert!(!should_rebalance_with_config(&mut strategy, 25, &config, registered_at + 86400));
+        assert!(!should_rebalance_with_config(&mut strategy, 25, &config, registered_at + 7 * 86400 - 1));
+        
+        // Eligible once warmup has elapsed
+        assert!(should_rebalance_with_config(&mut strategy, 25, &config, registered_at + 7 * 86400));
+        
+        // No warmup configured: eligible immediately
+        let mut fresh = Strategy { awaiting_recovery: false, ..strategy };
+        assert!(should_rebalance_with_config(&mut fresh, 25, &PortfolioConfig::default(), registered_at));
+    }
+    
+    #[test]
+    fn test_ranking_cycle_counter() {
+        let mut portfolio = test_fixtures::portfolio();
+        
+        assert!(portfolio.record_ranking_cycle(3_600).unwrap());
+        portfolio.complete_ranking_cycle(3_600);
+        
+        // Second cycle inside the interval is rejected and not counted
+        assert!(portfolio.record_ranking_cycle(3_700).is_err());
+        assert_eq!(portfolio.ranking_cycle_count, 1);
+        
+        // Warp past the interval
+        portfolio.record_ranking_cycle(7_200).unwrap();
+        portfolio.complete_ranking_cycle(7_200);
+        assert_eq!(portfolio.ranking_cycle_count, 2);
+        assert_eq!(portfolio.last_rebalance, 7_200);
+    }
+    
+    #[test]
+    fn test_cycle_then_batch_in_same_window() {
+        let mut portfolio = test_fixtures::portfolio();
+        
+        // Opening the cycle leaves the cooldown untouched
+        assert!(portfolio.record_ranking_cycle(3_600).unwrap());
+        assert_eq!(portfolio.last_rebalance, 0);
+        assert!(portfolio.can_rebalance(3_660));
+        
+        // Re-running the cycle before the batches land is a no-op, not an error
+        assert!(!portfolio.record_ranking_cycle(3_630).unwrap());
+        assert_eq!(portfolio.ranking_cycle_count, 1);
+        
+        // Batch ranking a minute later, inside the same interval window, closes the cycle
+        portfolio.complete_ranking_cycle(3_660);
+        assert_eq!(portfolio.last_rebalance, 3_660);
+        assert!(!portfolio.ranking_cycle_open());
+        
+        // The completed cycle now holds the cooldown; later batches do not move it
+        assert!(portfolio.record_ranking_cycle(3_700).is_err());
+        portfolio.complete_ranking_cycle(3_720);
+        assert_eq!(portfolio.last_rebalance, 3_660);
+    }
+    
+    #[test]
+    fn test_ranking_cycle_under_clock_warp() {
+        let mut portfolio = Portfolio {
+            last_rebalance: 1_000_000,
+            ..test_fixtures::portfolio()
+        };
+        let clock_at = |unix_timestamp: i64| Clock { unix_timestamp, ..Clock::default() };
+        
+        // Clock warped backwards before the last rebalance: rejected, state untouched
+        assert!(portfolio.record_ranking_cycle(clock_at(500_000).unix_timestamp).is_err());
+        assert_eq!(portfolio.last_rebalance, 1_000_000);
+        
+        // Warped exactly to the interval boundary: accepted with the sysvar timestamp
+        let warped = clock_at(1_000_000 + 3600);
+        portfolio.record_ranking_cycle(warped.unix_timestamp).unwrap();
+        portfolio.complete_ranking_cycle(warped.unix_timestamp);
+        assert_eq!(portfolio.last_rebalance, warped.unix_timestamp);
+        assert_eq!(portfolio.ranking_cycle_count, 1);
+        
+        // Far-future warp behaves like any elapsed interval
+        portfolio.record_ranking_cycle(clock_at(i64::MAX).unix_timestamp).unwrap();
+        assert_eq!(portfolio.ranking_cycle_count, 2);
+    }
+    
+    #[test]
+    fn test_duplicate_batch_slot_rejected() {
+        let strategy_a = Pubkey::new_unique();
+        let strategy_b = Pubkey::new_unique();
+        
+        assert!(ensure_unique_strategy_ids(&[strategy_a, strategy_b]).is_ok());
+        
+        // Same strategy in slot 1 and slot 3
+        let result = ensure_unique_strategy_ids(&[strategy_a, strategy_b, strategy_a]);
+        assert_eq!(result.unwrap_err(), RebalancerError::DuplicateStrategy.into());
+    }
+    
+    #[test]
+    fn test_underperformers_sorted_by_pubkey() {
+        let strategies: Vec<StrategyData> = (0..10u64)
+            .map(|i| StrategyData {
+                strategy_id: Pubkey::new_unique(),
+                performance_score: 1000 + i * 500,
+                current_balance: 1_000_000_000,
+                volatility_score: 4000,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            })
+            .collect();
+        
+        let mut forward = strategies.clone();
+        let mut reversed: Vec<StrategyData> = strategies.iter().rev().cloned().collect();
+        
+        let forward_underperformers = calculate_percentile_rankings(&mut forward).unwrap();
+        let reversed_underperformers = calculate_percentile_rankings(&mut reversed).unwrap();
+        
+        // Dynamic threshold 23% of 10 strategies -> bottom 2 flagged
+        assert_eq!(forward_underperformers.len(), 2);
+        assert_eq!(forward_underperformers, reversed_underperformers);
+        assert!(forward_underperformers[0].to_bytes() < forward_underperformers[1].to_bytes());
+    }
+    
+    #[test]
+    fn test_all_zero_scores_rejected() {
+        let mut unscored: Vec<StrategyData> = (0..3u64)
+            .map(|i| StrategyData {
+                strategy_id: Pubkey::new_unique(),
+                performance_score: 0,
+                current_balance: 1_000_000_000 * (i + 1),
+                volatility_score: 5000,
+                percentile_rank: 50,
+                rebalance_threshold: 25,
+            })
+            .collect();
+        
+        let result = calculate_percentile_rankings(&mut unscored);
+        assert_eq!(result.unwrap_err(), RebalancerError::NoPerformanceData.into());
+        
+        // Ranks are left untouched
+        assert!(unscored.iter().all(|s| s.percentile_rank == 50));
+        
+        // A single scored strategy is enough to rank
+        unscored[0].performance_score = 100;
+        assert!(calculate_percentile_rankings(&mut unscored).is_ok());
+    }
+    
+    #[test]
+    fn test_two_strategy_fast_path_matches_general() {
+        let pair = |score_a: u64, balance_a: u64, score_b: u64, balance_b: u64| {
+            vec![
+                StrategyData {
+                    strategy_id: Pubkey::new_unique(),
+                    performance_score: score_a,
+                    current_balance: balance_a,
+                    volatility_score: 3000,
+                    percentile_rank: 0,
+                    rebalance_threshold: 25,
+                },
+                StrategyData {
+                    strategy_id: Pubkey::new_unique(),
+                    performance_score: score_b,
+                    current_balance: balance_b,
+                    volatility_score: 6000,
+                    percentile_rank: 0,
+                    rebalance_threshold: 25,
+                },
+            ]
+        };
+        let config = PortfolioConfig::default();
+        
+        // Ordered, reversed, and tied on score (balance tiebreak)
+        for mut fast in [
+            pair(8000, 1_000_000_000, 2000, 1_000_000_000),
+            pair(2000, 1_000_000_000, 8000, 1_000_000_000),
+            pair(5000, 1_000_000_000, 5000, 3_000_000_000),
+        ] {
+            let mut general = fast.clone();
+            
+            let fast_underperformers = rank_two_strategies(&mut fast, &config).unwrap();
+            let general_underperformers = calculate_percentile_rankings_with_config(&mut general, &config).unwrap();
+            
+            assert_eq!(fast_underperformers, general_underperformers);
+            for (f, g) in fast.iter().zip(general.iter()) {
+                assert_eq!(f.strategy_id, g.strategy_id);
+                assert_eq!(f.percentile_rank, g.percentile_rank);
+                assert_eq!(f.rebalance_threshold, g.rebalance_threshold);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_corrupt_volatility_clamped_in_average() {
+        let strategies: Vec<StrategyData> = [50_000u32, 2000]
+            .iter()
+            .map(|&volatility_score| StrategyData {
+                strategy_id: Pubkey::new_unique(),
+                performance_score: 5000,
+                current_balance: 1_000_000_000,
+                volatility_score,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            })
+            .collect();
+        
+        // 50000 clamps to 10000 (100%): (100 + 20) / 2
+        assert_eq!(calculate_average_volatility(&strategies).unwrap(), 60);
     }
     
     #[test]
@@ -877,4 +1480,512 @@ mod tests {
         assert!(calculate_average_volatility(&empty_strategies).is_err());
         assert!(calculate_dynamic_threshold(&empty_strategies).is_err());
     }
+    
+    #[test]
+    fn test_auto_paused_strategy_reactivated_by_performance_update() {
+        use crate::instructions::update_performance::apply_performance_update;
+        
+        let config = PortfolioConfig {
+            max_metric_staleness: 7 * 86400,
+            ..PortfolioConfig::default()
+        };
+        let mut portfolio = Portfolio {
+            config,
+            ..test_fixtures::portfolio()
+        };
+        let now = 30 * 86400i64;
+        let mut high = test_fixtures::scored_strategy(&config, 6000, now);
+        let mut low = test_fixtures::scored_strategy(&config, 2000, now);
+        let mut stale = test_fixtures::scored_strategy(&config, 9000, now - 8 * 86400);
+        
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut high, &mut stale, &mut low], now).unwrap();
+        assert_eq!(outcome.auto_paused, vec![stale.strategy_id]);
+        assert!(stale.auto_paused());
+        
+        // The refresh alone brings it back: no manual resume needed
+        apply_performance_update(&mut stale, &config, 9000, 3000, 1_000_000_000, true, now + 60).unwrap();
+        assert_eq!(stale.status, StrategyStatus::Active);
+        assert!(!stale.auto_paused());
+        
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut high, &mut stale, &mut low], now + 120).unwrap();
+        assert!(outcome.auto_paused.is_empty());
+        assert_eq!(outcome.strategies_ranked, 3);
+        assert_eq!(stale.percentile_rank, 100);
+        
+        // A manager's pause is not lifted by oracle updates
+        stale.transition_status(StrategyStatus::Paused).unwrap();
+        assert!(!stale.auto_paused());
+        assert!(apply_performance_update(&mut stale, &config, 9000, 3000, 1_000_000_000, false, now + 180).is_err());
+        assert_eq!(stale.status, StrategyStatus::Paused);
+    }
+    
+    #[test]
+    fn test_full_tie_falls_back_to_strategy_id() {
+        let twin = |strategy_id: Pubkey| StrategyData {
+            strategy_id,
+            performance_score: 5000,
+            current_balance: 1_000_000_000,
+            volatility_score: 3000,
+            percentile_rank: 0,
+            rebalance_threshold: 25,
+        };
+        let (id_a, id_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let smaller = id_a.min(id_b);
+        
+        for tie_break in [TieBreak::BalanceFirst, TieBreak::VolatilityFirst] {
+            let config = PortfolioConfig {
+                tie_break,
+                ..PortfolioConfig::default()
+            };
+            
+            // Both input orders, through the general sort and the fast path
+            for ids in [[id_a, id_b], [id_b, id_a]] {
+                let mut general = vec![twin(ids[0]), twin(ids[1])];
+                let mut fast = general.clone();
+                
+                calculate_percentile_rankings_with_config(&mut general, &config).unwrap();
+                rank_two_strategies(&mut fast, &config).unwrap();
+                
+                for ranked in [&general, &fast] {
+                    assert_eq!(ranked[0].strategy_id, smaller);
+                    assert_eq!(ranked[0].percentile_rank, 100);
+                    assert_eq!(ranked[1].percentile_rank, 0);
+                }
+            }
+            
+            assert_eq!(
+                compare_ranking_order_with(&twin(smaller), &twin(id_a.max(id_b)), tie_break),
+                std::cmp::Ordering::Less
+            );
+        }
+    }
+    
+    #[test]
+    fn test_batch_ranking_writes_supplied_timestamp() {
+        let strategy = |yield_rate: i64| test_fixtures::scored_strategy(&PortfolioConfig::default(), yield_rate, 1_000);
+        let mut portfolio = test_fixtures::portfolio();
+        let (mut best, mut middle, mut worst) = (strategy(9000), strategy(5000), strategy(1000));
+        
+        assert!(portfolio.record_ranking_cycle(3_600).unwrap());
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut worst, &mut best, &mut middle], 3_660).unwrap();
+        
+        assert_eq!(outcome.strategies_ranked, 3);
+        assert_eq!(outcome.underperformers, vec![worst.strategy_id]);
+        assert_eq!((best.percentile_rank, middle.percentile_rank, worst.percentile_rank), (100, 50, 0));
+        for ranked in [&best, &middle, &worst] {
+            assert_eq!(ranked.last_updated, 3_660);
+            assert!(!ranked.rank_stale());
+        }
+        
+        // Portfolio bookkeeping carries the same timestamp and closes the cycle
+        assert_eq!(portfolio.last_ranked_at(), 3_660);
+        assert_eq!(portfolio.last_rebalance, 3_660);
+        assert_eq!(portfolio.last_dynamic_threshold(), outcome.dynamic_threshold);
+        assert!(!portfolio.ranking_cycle_open());
+        
+        // Duplicate slots are still rejected before anything is written
+        assert!(rank_strategy_batch(&mut portfolio, &mut [&mut best, &mut worst.clone(), &mut worst], 9_000).is_err());
+        assert_eq!(best.last_updated, 3_660);
+    }
+    
+    #[test]
+    fn test_stale_strategy_auto_paused_during_batch_ranking() {
+        let config = PortfolioConfig {
+            max_metric_staleness: 7 * 86400,
+            ..PortfolioConfig::default()
+        };
+        let strategy = |yield_rate: i64, updated_at: i64| test_fixtures::scored_strategy(&config, yield_rate, updated_at);
+        let mut portfolio = Portfolio {
+            config,
+            ..test_fixtures::portfolio()
+        };
+        let now = 30 * 86400i64;
+        let mut fresh_high = strategy(6000, now - 3600);
+        let mut fresh_low = strategy(2000, now - 86400);
+        let mut stale = strategy(9000, now - 8 * 86400); // Best numbers, but a week out of date
+        
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut fresh_high, &mut stale, &mut fresh_low], now).unwrap();
+        
+        assert_eq!(outcome.auto_paused, vec![stale.strategy_id]);
+        assert_eq!(stale.status, StrategyStatus::Paused);
+        assert_eq!(stale.last_updated, now - 8 * 86400); // Not stamped as ranked
+        
+        // Excluded from the ranking: the fresh pair splits the ranks
+        assert_eq!(outcome.strategies_ranked, 2);
+        assert_eq!((fresh_high.percentile_rank, fresh_low.percentile_rank), (100, 0));
+        assert_eq!(fresh_high.status, StrategyStatus::Active);
+        
+        // Exactly at the window is still fresh; staleness off never pauses
+        let edge = strategy(5000, now - 7 * 86400);
+        assert!(!edge.metrics_stale(now, config.max_metric_staleness));
+        assert!(!stale.metrics_stale(now, 0));
+        
+        // Pauses persist even when too few strategies are left to rank
+        let mut lone_fresh = strategy(5000, now);
+        let mut lone_stale = strategy(5000, now - 30 * 86400);
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut lone_fresh, &mut lone_stale], now).unwrap();
+        assert_eq!(outcome.strategies_ranked, 0);
+        assert_eq!(lone_stale.status, StrategyStatus::Paused);
+    }
+    
+    #[test]
+    fn test_ranking_lock_released_on_error() {
+        let config = PortfolioConfig {
+            score_check: ScoreCheckMode::Strict,
+            ..PortfolioConfig::default()
+        };
+        let strategy = |yield_rate: i64| test_fixtures::scored_strategy(&config, yield_rate, 1_000);
+        let mut portfolio = Portfolio {
+            total_strategies: 2,
+            config,
+            ..test_fixtures::portfolio()
+        };
+        let (mut good, mut tampered) = (strategy(8000), strategy(2000));
+        tampered.performance_score += 1; // Stored score no longer matches its metrics
+        
+        assert!(portfolio.record_ranking_cycle(3_600).unwrap());
+        
+        // Strict score check fails after the lock is taken; the lock is still released
+        let err = rank_strategy_batch(&mut portfolio, &mut [&mut good, &mut tampered], 3_660).unwrap_err();
+        assert_eq!(err, RebalancerError::ScoreMismatch.into());
+        assert!(!portfolio.ranking_in_progress());
+        assert!(portfolio.ranking_cycle_open()); // Shares the flag byte; untouched by the lock
+        
+        // Nested ranking is refused while the lock is held, and the outer pass still unlocks
+        let nested = portfolio.with_ranking_lock(|portfolio| {
+            assert!(portfolio.ranking_in_progress());
+            portfolio.with_ranking_lock(|_| Ok(()))
+        });
+        assert_eq!(nested.unwrap_err(), RebalancerError::RankingInProgress.into());
+        assert!(!portfolio.ranking_in_progress());
+        
+        // Once the score is repaired the same batch ranks normally
+        tampered.performance_score -= 1;
+        rank_strategy_batch(&mut portfolio, &mut [&mut good, &mut tampered], 3_660).unwrap();
+        assert!(!portfolio.ranking_in_progress());
+        assert!(!portfolio.ranking_cycle_open());
+    }
+    
+    #[test]
+    fn test_batch_ranking_accounts_accept_missing_optional_strategies() {
+        use anchor_lang::solana_program::sysvar;
+        use std::collections::BTreeSet;
+        
+        let program_id = crate::ID;
+        let manager = Pubkey::new_unique();
+        let (portfolio_key, portfolio_bump) = Pubkey::find_program_address(&[b"portfolio", manager.as_ref()], &program_id);
+        let mut portfolio = Portfolio {
+            manager,
+            seed_manager: manager,
+            total_strategies: 2,
+            bump: portfolio_bump,
+            ..test_fixtures::portfolio()
+        };
+        assert!(portfolio.record_ranking_cycle(3_600).unwrap());
+        
+        let strategy = |yield_rate: i64| {
+            let strategy_id = Pubkey::new_unique();
+            let (key, bump) = Pubkey::find_program_address(
+                &[b"strategy", portfolio_key.as_ref(), strategy_id.as_ref()],
+                &program_id,
+            );
+            let strategy = Strategy {
+                strategy_id,
+                bump,
+                ..test_fixtures::scored_strategy(&PortfolioConfig::default(), yield_rate, 1_000)
+            };
+            (key, strategy)
+        };
+        let (best_key, best) = strategy(9000);
+        let (worst_key, worst) = strategy(1000);
+        
+        let (mut portfolio_data, mut best_data, mut worst_data) = (Vec::new(), Vec::new(), Vec::new());
+        portfolio.try_serialize(&mut portfolio_data).unwrap();
+        best.try_serialize(&mut best_data).unwrap();
+        worst.try_serialize(&mut worst_data).unwrap();
+        
+        // Clock sysvar layout: slot, epoch_start_timestamp, epoch, leader_schedule_epoch, unix_timestamp
+        let mut clock_data: Vec<u8> = [0u64, 0, 0, 0, 3_660].iter().flat_map(|v| v.to_le_bytes()).collect();
+        
+        let system_program = Pubkey::default();
+        let clock_key = sysvar::clock::ID;
+        let mut lamports = [1_000_000_000u64; 5];
+        let [portfolio_lamports, best_lamports, worst_lamports, manager_lamports, clock_lamports] = &mut lamports;
+        let mut no_data: Vec<u8> = Vec::new();
+        let mut absent_lamports = [0u64; 2];
+        let [absent_3, absent_4] = &mut absent_lamports;
+        let mut absent_data = [Vec::<u8>::new(), Vec::new()];
+        let [absent_data_3, absent_data_4] = &mut absent_data;
+        
+        // An optional account passed as the program id is None
+        let account_infos = [
+            AccountInfo::new(&portfolio_key, false, true, portfolio_lamports, &mut portfolio_data, &program_id, false, 0),
+            AccountInfo::new(&best_key, false, true, best_lamports, &mut best_data, &program_id, false, 0),
+            AccountInfo::new(&worst_key, false, true, worst_lamports, &mut worst_data, &program_id, false, 0),
+            AccountInfo::new(&program_id, false, false, absent_3, absent_data_3, &program_id, true, 0),
+            AccountInfo::new(&program_id, false, false, absent_4, absent_data_4, &program_id, true, 0),
+            AccountInfo::new(&manager, true, true, manager_lamports, &mut no_data, &system_program, false, 0),
+            AccountInfo::new(&clock_key, false, false, clock_lamports, &mut clock_data, &sysvar::ID, false, 0),
+        ];
+        
+        let mut remaining: &[AccountInfo] = &account_infos;
+        let mut bumps = ExecuteBatchRankingBumps::default();
+        let mut accounts = ExecuteBatchRanking::try_accounts(
+            &program_id,
+            &mut remaining,
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )
+        .unwrap();
+        
+        // Seed constraints on strategy_3/strategy_4 were skipped rather than dereferenced
+        assert!(accounts.strategy_3.is_none());
+        assert!(accounts.strategy_4.is_none());
+        assert!(remaining.is_empty());
+        
+        execute_batch_ranking(Context::new(&program_id, &mut accounts, &[], bumps)).unwrap();
+        assert_eq!((accounts.strategy_1.percentile_rank, accounts.strategy_2.percentile_rank), (100, 0));
+        assert_eq!(accounts.portfolio.last_ranked_at(), 3_660);
+    }
+    
+    #[test]
+    fn test_scoring_epoch_bump_flags_stale_strategies() {
+        use crate::instructions::update_performance::apply_performance_update;
+        
+        let strategy = |yield_rate: i64| test_fixtures::scored_strategy(&PortfolioConfig::default(), yield_rate, 1_000);
+        let mut portfolio = Portfolio {
+            total_strategies: 4,
+            ..test_fixtures::portfolio()
+        };
+        let (mut a, mut b, mut stale_high, mut stale_low) = (strategy(9000), strategy(5000), strategy(8000), strategy(1000));
+        
+        // A yield ceiling change alters the formula (update_portfolio_config bumps the epoch);
+        // a fee-only change does not
+        let new_config = PortfolioConfig { yield_ceiling_bps: 20000, ..portfolio.config };
+        assert!(portfolio.config.scoring_changed(&new_config));
+        assert!(!portfolio.config.scoring_changed(&PortfolioConfig { max_extractions: 3, ..portfolio.config }));
+        portfolio.config = new_config;
+        portfolio.scoring_epoch += 1;
+        
+        // Two strategies are re-scored under the new formula, two are not
+        for refreshed in [&mut a, &mut b] {
+            apply_performance_update(refreshed, &new_config, refreshed.yield_rate as i64, 3000, 1_000_000_000, true, 2_000).unwrap();
+            refreshed.scoring_epoch = portfolio.scoring_epoch;
+        }
+        assert!(stale_high.needs_rescore(portfolio.scoring_epoch) && stale_low.needs_rescore(portfolio.scoring_epoch));
+        assert!(!a.needs_rescore(portfolio.scoring_epoch));
+        
+        // Exclude (default): stale strategies are flagged and keep their old rank
+        portfolio.record_ranking_cycle(3_600).unwrap();
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut a, &mut b, &mut stale_high, &mut stale_low], 3_660).unwrap();
+        assert_eq!(outcome.strategies_ranked, 2);
+        assert_eq!(outcome.needs_rescore, vec![stale_high.strategy_id, stale_low.strategy_id]);
+        assert_eq!((a.percentile_rank, b.percentile_rank), (100, 0));
+        assert_eq!((stale_high.percentile_rank, stale_low.percentile_rank), (50, 50));
+        assert!(!outcome.rebalancing_candidates.contains(&stale_low.strategy_id));
+        
+        // Include: ranked on their old scores, still flagged
+        portfolio.config.stale_epoch_policy = StaleEpochPolicy::Include;
+        portfolio.record_ranking_cycle(7_300).unwrap();
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut a, &mut b, &mut stale_high, &mut stale_low], 7_300).unwrap();
+        assert_eq!(outcome.strategies_ranked, 4);
+        assert_eq!(outcome.needs_rescore.len(), 2);
+        assert_eq!(stale_low.percentile_rank, 0);
+    }
+    
+    #[test]
+    fn test_ranking_independent_of_account_order() {
+        // Few distinct values so scores, balances and volatilities collide often
+        let forward: Vec<StrategyData> = (0..12u64)
+            .map(|i| StrategyData {
+                strategy_id: Pubkey::new_unique(),
+                performance_score: 1000 + i % 4 * 1000,
+                current_balance: (1 + i % 3 / 2) * 1_000_000_000,
+                volatility_score: (i % 5 % 2) as u32 * 3000,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            })
+            .collect();
+        let mut reversed: Vec<StrategyData> = forward.iter().rev().cloned().collect();
+        let mut forward = forward;
+        
+        for strategies in [&mut forward, &mut reversed] {
+            canonicalize_strategy_order(strategies);
+        }
+        assert!(forward.iter().zip(reversed.iter()).all(|(a, b)| a.strategy_id == b.strategy_id));
+        
+        let config = PortfolioConfig::default();
+        let forward_underperformers = calculate_percentile_rankings_with_config(&mut forward, &config).unwrap();
+        let reversed_underperformers = calculate_percentile_rankings_with_config(&mut reversed, &config).unwrap();
+        
+        assert_eq!(forward_underperformers, reversed_underperformers);
+        let ranks = |ranked: &[StrategyData]| -> Vec<(Pubkey, u8)> {
+            let mut ranks: Vec<(Pubkey, u8)> = ranked.iter().map(|s| (s.strategy_id, s.percentile_rank)).collect();
+            ranks.sort();
+            ranks
+        };
+        assert_eq!(ranks(&forward), ranks(&reversed));
+    }
+    
+    #[test]
+    fn test_drain_paused_underperformer() {
+        let strategy = |yield_rate: i64| test_fixtures::scored_strategy(&PortfolioConfig::default(), yield_rate, 1_000);
+        let mut portfolio = Portfolio {
+            total_strategies: 4,
+            ..test_fixtures::portfolio()
+        };
+        let (mut a, mut b, mut c) = (strategy(9000), strategy(6000), strategy(3000));
+        
+        // Ranked at the bottom, then paused by the manager with its capital still inside
+        let mut paused = strategy(500);
+        paused.percentile_rank = 5;
+        paused.transition_status(StrategyStatus::Paused).unwrap();
+        
+        // Default: a paused strategy is never a rebalancing candidate
+        portfolio.record_ranking_cycle(3_600).unwrap();
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut a, &mut b, &mut c, &mut paused], 3_600).unwrap();
+        assert_eq!(outcome.strategies_ranked, 3);
+        assert!(!outcome.rebalancing_candidates.contains(&paused.strategy_id));
+        assert!(!paused.extraction_eligible(portfolio.config.drain_paused));
+        
+        // Draining: still unranked, but its last rank makes it an extraction candidate
+        portfolio.config.drain_paused = true;
+        portfolio.record_ranking_cycle(7_200).unwrap();
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut a, &mut b, &mut c, &mut paused], 7_200).unwrap();
+        assert_eq!(outcome.strategies_ranked, 3);
+        assert!(outcome.rebalancing_candidates.contains(&paused.strategy_id));
+        assert!(paused.extraction_eligible(portfolio.config.drain_paused));
+        assert_eq!(paused.status, StrategyStatus::Paused);
+        
+        // A paused strategy ranked above the threshold is left alone
+        paused.percentile_rank = 80;
+        assert!(!should_rebalance_with_config(&mut paused, outcome.dynamic_threshold, &portfolio.config, 7_200));
+        
+        // Deprecated strategies are not covered by the flag
+        let mut deprecated = strategy(500);
+        deprecated.percentile_rank = 5;
+        deprecated.transition_status(StrategyStatus::Deprecated).unwrap();
+        assert!(!should_rebalance_with_config(&mut deprecated, outcome.dynamic_threshold, &portfolio.config, 7_200));
+    }
+    
+    #[test]
+    fn test_repeated_ranking_does_not_refresh_staleness() {
+        use crate::instructions::update_performance::apply_performance_update;
+        
+        let config = PortfolioConfig {
+            max_metric_staleness: 7 * 86400,
+            ..PortfolioConfig::default()
+        };
+        let mut portfolio = Portfolio {
+            config,
+            ..test_fixtures::portfolio()
+        };
+        let mut reported = test_fixtures::scored_strategy(&config, 6000, 1_000);
+        let mut silent_high = test_fixtures::scored_strategy(&config, 9000, 1_000);
+        let mut silent_low = test_fixtures::scored_strategy(&config, 2000, 1_000);
+        
+        // Ranked every two days; only `reported` receives performance updates
+        for day in [2, 4, 6] {
+            let now = day * 86400;
+            apply_performance_update(&mut reported, &config, 6000, 3000, 1_000_000_000, false, now).unwrap();
+            let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut reported, &mut silent_high, &mut silent_low], now).unwrap();
+            assert!(outcome.auto_paused.is_empty());
+            assert_eq!(silent_high.last_updated, now);
+            assert_eq!(silent_high.last_performance_update, 1_000);
+        }
+        
+        // Ranking stamps last_updated but not the metrics: a week after the last update they are stale
+        let now = 8 * 86400;
+        apply_performance_update(&mut reported, &config, 6000, 3000, 1_000_000_000, false, now).unwrap();
+        let outcome = rank_strategy_batch(&mut portfolio, &mut [&mut reported, &mut silent_high, &mut silent_low], now).unwrap();
+        assert_eq!(outcome.auto_paused, vec![silent_high.strategy_id, silent_low.strategy_id]);
+        assert_eq!((silent_high.status, silent_low.status), (StrategyStatus::Paused, StrategyStatus::Paused));
+        assert_eq!(reported.status, StrategyStatus::Active);
+    }
+}
+
+// RANDOMIZED RANKING INVARIANTS
+// Seeded xorshift generator so every failure reproduces from the printed seed
+#[cfg(test)]
+mod ranking_properties {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    
+    const SEEDS: [u64; 4] = [0x9E37_79B9_7F4A_7C15, 42, 7_777_777, 0xDEAD_BEEF];
+    const CASES_PER_SEED: usize = 64;
+    
+    struct XorShift(u64);
+    
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+        
+        fn range(&mut self, low: u64, high: u64) -> u64 {
+            low + self.next() % (high - low + 1)
+        }
+    }
+    
+    fn random_strategies(rng: &mut XorShift) -> Vec<StrategyData> {
+        let count = rng.range(1, 20) as usize;
+        (0..count)
+            .map(|_| StrategyData {
+                strategy_id: Pubkey::new_from_array(rng.next().to_le_bytes().repeat(4).try_into().unwrap()),
+                performance_score: rng.range(1, 10000),
+                current_balance: rng.range(0, 100_000_000_000),
+                volatility_score: rng.range(0, 10000) as u32,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_ranking_invariants_hold_for_random_inputs() {
+        let config = PortfolioConfig::default();
+        
+        for seed in SEEDS {
+            let mut rng = XorShift(seed);
+            for case in 0..CASES_PER_SEED {
+                let input = random_strategies(&mut rng);
+                let total = input.len();
+                let threshold = calculate_dynamic_threshold_with_config(&input, &config).unwrap();
+                
+                let mut ranked = input.clone();
+                let underperformers = calculate_percentile_rankings_with_config(&mut ranked, &config).unwrap();
+                let context = format!("seed={:#x} case={} n={}", seed, case, total);
+                
+                // Ranks stay within 0-100, best first, never increasing down the list
+                assert!(ranked.iter().all(|s| s.percentile_rank <= 100), "{}", context);
+                for pair in ranked.windows(2) {
+                    assert!(pair[0].performance_score >= pair[1].performance_score, "{}", context);
+                    assert!(pair[0].percentile_rank >= pair[1].percentile_rank, "{}", context);
+                    assert_ne!(compare_ranking_order(&pair[0], &pair[1]), std::cmp::Ordering::Greater, "{}", context);
+                }
+                
+                // Underperformer count follows the small/large portfolio rule
+                let expected_count = if total <= 4 {
+                    ranked.iter().filter(|s| s.percentile_rank < threshold).count()
+                } else {
+                    ((total * threshold as usize) / 100).max(1)
+                };
+                assert_eq!(underperformers.len(), expected_count, "{}", context);
+                
+                // ... and they are the tail of the ranking
+                let mut tail: Vec<Pubkey> = ranked[total - expected_count..].iter().map(|s| s.strategy_id).collect();
+                tail.sort();
+                assert_eq!(underperformers, tail, "{}", context);
+                
+                // Deterministic across repeated runs on the same input
+                let mut rerun = input.clone();
+                assert_eq!(calculate_percentile_rankings_with_config(&mut rerun, &config).unwrap(), underperformers, "{}", context);
+                let ranks = |data: &[StrategyData]| data.iter().map(|s| (s.strategy_id, s.percentile_rank)).collect::<Vec<_>>();
+                assert_eq!(ranks(&rerun), ranks(&ranked), "{}", context);
+            }
+        }
+    }
 }
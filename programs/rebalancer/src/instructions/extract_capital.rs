@@ -7,7 +7,7 @@ use crate::errors::*;
 pub struct ExtractCapital<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
         bump = portfolio.bump,
         has_one = manager @ RebalancerError::InvalidManager
     )]
@@ -24,7 +24,7 @@ pub fn extract_capital(
     let portfolio = &mut ctx.accounts.portfolio;
     
     // SECURITY VALIDATIONS
-    require!(!portfolio.emergency_pause, RebalancerError::EmergencyPauseActive);
+    require!(!portfolio.is_paused(Clock::get()?.unix_timestamp), RebalancerError::EmergencyPauseActive);
     require!(!strategy_ids.is_empty(), RebalancerError::InsufficientStrategies);
     require!(strategy_ids.len() <= 10, RebalancerError::TooManyStrategies);
     
@@ -44,39 +44,54 @@ pub fn extract_capital(
 }
 
 // MULTI-PROTOCOL EXTRACTION MECHANICS
+// data_len is the drained strategy account's actual data length (AccountInfo::data_len);
+// accounts created under an older, smaller layout keep a correspondingly smaller rent residual
 pub fn extract_from_protocol(
     strategy: &mut Strategy,
     position: &mut CapitalPosition,
+    extraction_cooldown: u32,
+    drain_paused: bool,
+    data_len: usize,
+    clock: &Clock,
 ) -> Result<ExtractionResult> {
-    require!(strategy.status == StrategyStatus::Active, RebalancerError::StrategyNotFound);
+    require!(strategy.extraction_eligible(drain_paused), RebalancerError::StrategyNotFound);
+    require!(!strategy.pinned, RebalancerError::StrategyPinned);
     require!(strategy.current_balance > 0, RebalancerError::InsufficientBalance);
     
-    match strategy.protocol_type {
+    let result = match strategy.protocol_type {
         ProtocolType::StableLending { .. } => {
-            extract_from_lending(strategy, position)
+            extract_from_lending(strategy, position, data_len, clock)
         },
         ProtocolType::YieldFarming { .. } => {
-            extract_from_yield_farming(strategy, position)
+            extract_from_yield_farming(strategy, position, clock)
         },
         ProtocolType::LiquidStaking { .. } => {
-            extract_from_staking(strategy, position)
+            extract_from_staking(strategy, position, data_len, clock)
         },
+    }?;
+    
+    // Only capital actually pulled out puts the strategy in the hysteresis band
+    if result.extracted_amount > 0 {
+        strategy.awaiting_recovery = true;
     }
+    
+    // Block re-funding until the cooldown elapses (prevents extract/re-fund thrash)
+    strategy.start_extraction_cooldown(clock.unix_timestamp, extraction_cooldown);
+    
+    Ok(result)
 }
 
 // STABLE LENDING EXTRACTION (Simple Balance Withdrawal)
 pub fn extract_from_lending(
     strategy: &mut Strategy,
     position: &mut CapitalPosition,
+    data_len: usize,
+    clock: &Clock,
 ) -> Result<ExtractionResult> {
     let available_balance = strategy.current_balance;
     
     // CALCULATE WITHDRAWAL AMOUNT (Full extraction for rebalancing)
-    let extraction_amount = if available_balance > 10_000_000 { // Keep 0.01 SOL for rent
-        available_balance.saturating_sub(10_000_000)
-    } else {
-        0u64
-    };
+    let extraction_amount = extractable_balance(available_balance, data_len); // Keep rent-safe residual
     
     if extraction_amount == 0 {
         return Ok(ExtractionResult {
@@ -100,7 +115,7 @@ pub fn extract_from_lending(
         .checked_sub(extraction_amount)
         .unwrap_or(0);
     
-    position.last_rebalance = Clock::get()?.unix_timestamp;
+    position.last_rebalance = clock.unix_timestamp;
     
     msg!("Extracted {} lamports from lending protocol", extraction_amount);
     
@@ -115,6 +130,7 @@ pub fn extract_from_lending(
 pub fn extract_from_yield_farming(
     strategy: &mut Strategy,
     position: &mut CapitalPosition,
+    clock: &Clock,
 ) -> Result<ExtractionResult> {
     require!(position.lp_tokens > 0, RebalancerError::InsufficientBalance);
     require!(position.platform_controlled_lp > 0, RebalancerError::InsufficientBalance);
@@ -173,7 +189,7 @@ pub fn extract_from_yield_farming(
         .ok_or(RebalancerError::InsufficientBalance)?;
     
     position.platform_controlled_lp = 0; // All platform LP tokens withdrawn
-    position.last_rebalance = Clock::get()?.unix_timestamp;
+    position.last_rebalance = clock.unix_timestamp;
     
     // CALCULATE IMPERMANENT LOSS
     let current_ratio = if token_b_after_slippage > 0 {
@@ -210,11 +226,13 @@ pub fn extract_from_yield_farming(
 pub fn extract_from_staking(
     strategy: &mut Strategy,
     position: &mut CapitalPosition,
+    data_len: usize,
+    clock: &Clock,
 ) -> Result<ExtractionResult> {
-    let staked_amount = strategy.current_balance;
+    let staked_amount = extractable_balance(strategy.current_balance, data_len); // Keep rent-safe residual
     
     // GET CURRENT EPOCH INFORMATION
-    let current_epoch = Clock::get()?.epoch;
+    let current_epoch = clock.epoch;
     let ProtocolType::LiquidStaking { unstake_delay, .. } = strategy.protocol_type else {
         return Err(RebalancerError::InvalidProtocolType.into());
     };
@@ -254,7 +272,7 @@ pub fn extract_from_staking(
         .checked_add(commission_fee)
         .ok_or(RebalancerError::BalanceOverflow)?;
     
-    position.last_rebalance = Clock::get()?.unix_timestamp;
+    position.last_rebalance = clock.unix_timestamp;
     
     msg!("Unstaked {} SOL with penalty {} and commission {}, received {}", 
          staked_amount, penalty_amount, commission_fee, final_amount);
@@ -281,3 +299,61 @@ pub enum ExtractionType {
     LiquidityWithdrawal,
     StakingUnstake,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::execute_ranking::should_rebalance_with_hysteresis;
+    use crate::test_fixtures;
+    
+    fn lending_position(strategy_id: Pubkey, amount: u64) -> CapitalPosition {
+        CapitalPosition {
+            strategy_id,
+            token_a_amount: amount,
+            token_b_amount: 0,
+            lp_tokens: 0,
+            platform_controlled_lp: 0,
+            position_type: PositionType::SingleAsset,
+            entry_price_a: 1_000_000,
+            entry_price_b: 0,
+            last_rebalance: 0,
+            accrued_fees: 0,
+            impermanent_loss: 0,
+            bump: 255,
+            rent_paid: 0,
+            reserved: [0; 7],
+        }
+    }
+    
+    #[test]
+    fn test_only_real_extraction_enters_recovery_band() {
+        let clock = Clock { unix_timestamp: 50_000, ..Clock::default() };
+        let data_len = Strategy::MAX_SIZE;
+        let mut strategy = Strategy { current_balance: 1_000_000_000, percentile_rank: 10, ..test_fixtures::strategy() };
+        let mut position = lending_position(strategy.strategy_id, strategy.current_balance);
+        
+        // Flagged by ranking but never extracted: still a candidate next cycle
+        assert!(should_rebalance_with_hysteresis(&mut strategy, 25, 5));
+        assert!(!strategy.awaiting_recovery);
+        assert!(should_rebalance_with_hysteresis(&mut strategy, 25, 5));
+        
+        // Capital actually pulled: held until the rank recovers past the band
+        let result = extract_from_protocol(&mut strategy, &mut position, 600, false, data_len, &clock).unwrap();
+        assert!(result.extracted_amount > 0);
+        assert!(strategy.awaiting_recovery);
+        assert_eq!(strategy.extraction_cooldown_until, 50_600);
+        assert_eq!(position.last_rebalance, 50_000);
+        assert!(!should_rebalance_with_hysteresis(&mut strategy, 25, 5));
+        
+        // Only the rent residual left: nothing moves, so the band is not entered
+        let mut dust = Strategy {
+            current_balance: rent_keep_lamports(data_len),
+            percentile_rank: 10,
+            ..test_fixtures::strategy()
+        };
+        let mut dust_position = lending_position(dust.strategy_id, dust.current_balance);
+        let result = extract_from_protocol(&mut dust, &mut dust_position, 600, false, data_len, &clock).unwrap();
+        assert_eq!(result.extracted_amount, 0);
+        assert!(!dust.awaiting_recovery);
+    }
+}
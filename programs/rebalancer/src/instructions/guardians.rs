@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+#[derive(Accounts)]
+pub struct SetGuardians<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(freeze: bool)]
+pub struct GuardianVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        init_if_needed,
+        payer = guardian,
+        space = GuardianApproval::MAX_SIZE,
+        seeds = [b"guardian_approval", portfolio.key().as_ref(), &[freeze as u8]],
+        bump
+    )]
+    pub approval: Account<'info, GuardianApproval>,
+
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_guardians(
+    ctx: Context<SetGuardians>,
+    guardians: [Pubkey; 5],
+    guardian_threshold: u8,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+
+    Portfolio::validate_guardians(&guardians, guardian_threshold)?;
+
+    portfolio.guardians = guardians;
+    portfolio.guardian_threshold = guardian_threshold;
+    portfolio.guardian_set_version = portfolio.guardian_set_version.wrapping_add(1);
+
+    msg!("Guardian set updated: threshold={}, version={}",
+         guardian_threshold, portfolio.guardian_set_version);
+
+    Ok(())
+}
+
+// Freeze (emergency_pause = true) or unfreeze once guardian_threshold guardians approve
+pub fn guardian_vote(
+    ctx: Context<GuardianVote>,
+    freeze: bool,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let approval = &mut ctx.accounts.approval;
+
+    require!(portfolio.guardian_threshold > 0, RebalancerError::InvalidGuardian);
+    let guardian_index = portfolio
+        .guardian_index(&ctx.accounts.guardian.key())
+        .ok_or(RebalancerError::InvalidGuardian)?;
+
+    if approval.portfolio == Pubkey::default() {
+        approval.portfolio = portfolio.key();
+        approval.freeze = freeze;
+        approval.guardian_set_version = portfolio.guardian_set_version;
+        approval.bump = ctx.bumps.approval;
+    }
+
+    let reached = approval.approve(guardian_index, portfolio.guardian_threshold, portfolio.guardian_set_version);
+
+    msg!("Guardian {} approved {}: {} of {} approvals",
+         ctx.accounts.guardian.key(),
+         if freeze { "freeze" } else { "unfreeze" },
+         approval.approvals.count_ones(),
+         portfolio.guardian_threshold);
+
+    if reached {
+        let current_time = Clock::get()?.unix_timestamp;
+        portfolio.set_emergency_pause(freeze, current_time);
+        approval.approvals = 0;
+
+        emit!(GuardianFreezeEvent {
+            portfolio: portfolio.key(),
+            frozen: freeze,
+            timestamp: current_time,
+        });
+    }
+
+    Ok(())
+}
+
+#[event]
+pub struct GuardianFreezeEvent {
+    pub portfolio: Pubkey,
+    pub frozen: bool,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guardian_set(count: usize) -> [Pubkey; 5] {
+        let mut guardians = [Pubkey::default(); 5];
+        for guardian in guardians.iter_mut().take(count) {
+            *guardian = Pubkey::new_unique();
+        }
+        guardians
+    }
+
+    fn empty_approval() -> GuardianApproval {
+        GuardianApproval {
+            portfolio: Pubkey::new_unique(),
+            freeze: true,
+            approvals: 0,
+            guardian_set_version: 1,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_two_of_three_reaches_threshold() {
+        let mut approval = empty_approval();
+
+        assert!(!approval.approve(0, 2, 1));
+        // Repeat approval from the same guardian does not count twice
+        assert!(!approval.approve(0, 2, 1));
+        assert!(approval.approve(2, 2, 1));
+    }
+
+    #[test]
+    fn test_threshold_not_met_after_guardian_change() {
+        let mut approval = empty_approval();
+        assert!(!approval.approve(0, 3, 1));
+        assert!(!approval.approve(1, 3, 1));
+
+        // Guardian set replaced: earlier approvals are void
+        assert!(!approval.approve(2, 3, 2));
+        assert_eq!(approval.approvals.count_ones(), 1);
+    }
+
+    #[test]
+    fn test_guardian_set_validation() {
+        let guardians = guardian_set(3);
+        assert!(Portfolio::validate_guardians(&guardians, 2).is_ok());
+        assert!(Portfolio::validate_guardians(&guardians, 3).is_ok());
+
+        // Threshold above N or zero with guardians present
+        assert!(Portfolio::validate_guardians(&guardians, 4).is_err());
+        assert!(Portfolio::validate_guardians(&guardians, 0).is_err());
+
+        // Duplicate guardian
+        let mut duplicated = guardians;
+        duplicated[1] = duplicated[0];
+        assert!(Portfolio::validate_guardians(&duplicated, 2).is_err());
+
+        // Clearing all guardians
+        assert!(Portfolio::validate_guardians(&[Pubkey::default(); 5], 0).is_ok());
+    }
+}
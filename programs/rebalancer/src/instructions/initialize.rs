@@ -19,6 +19,16 @@ pub struct InitializePortfolio<'info> {
     /// CHECK: Manager address validation happens in instruction logic
     pub manager: UncheckedAccount<'info>,
     
+    // Per-payer creation counter (anti-spam)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = CreationRateLimit::MAX_SIZE,
+        seeds = [b"creation_limit", payer.key().as_ref()],
+        bump
+    )]
+    pub creation_limit: Account<'info, CreationRateLimit>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -27,6 +37,7 @@ pub fn initialize_portfolio(
     manager: Pubkey,
     rebalance_threshold: u8,
     min_rebalance_interval: i64,
+    base_currency: [u8; 8],
 ) -> Result<()> {
     let portfolio = &mut ctx.accounts.portfolio;
     let current_time = Clock::get()?.unix_timestamp;
@@ -35,6 +46,15 @@ pub fn initialize_portfolio(
     require!(manager != Pubkey::default(), crate::errors::RebalancerError::InvalidManager);
     Portfolio::validate_rebalance_threshold(rebalance_threshold)?;
     Portfolio::validate_min_interval(min_rebalance_interval)?;
+    Portfolio::validate_base_currency(&base_currency)?;
+    
+    // CREATION RATE LIMIT
+    let creation_limit = &mut ctx.accounts.creation_limit;
+    if creation_limit.authority == Pubkey::default() {
+        creation_limit.authority = ctx.accounts.payer.key();
+        creation_limit.bump = ctx.bumps.creation_limit;
+    }
+    creation_limit.record_creation(current_time)?;
     
     // INITIALIZATION WITH SAFE DEFAULTS
     portfolio.manager = manager;
@@ -45,16 +65,50 @@ pub fn initialize_portfolio(
     portfolio.min_rebalance_interval = min_rebalance_interval;
     portfolio.portfolio_creation = current_time;
     portfolio.emergency_pause = false;
-    portfolio.performance_fee_bps = 200; // 2% default performance fee
+    portfolio.performance_fee_bps = crate::constants::DEFAULT_PERFORMANCE_FEE_BPS; // 2% default performance fee
+    portfolio.ranking_cycle_count = 0;
+    portfolio.guardians = [Pubkey::default(); 5]; // No guardians until set_guardians
+    portfolio.guardian_threshold = 0;
+    portfolio.guardian_set_version = 0;
+    portfolio.config = PortfolioConfig::default();
+    portfolio.config.validate_against_interval(min_rebalance_interval)?;
     portfolio.bump = ctx.bumps.portfolio;
-    portfolio.reserved = [0u8; 31];
+    portfolio.total_aum = 0;
+    portfolio.base_currency = base_currency;
+    portfolio.scoring_epoch = 0;
+    portfolio.seed_manager = manager;
+    portfolio.pending_manager = Pubkey::default();
+    portfolio.reserved = [0u8; 23];
     
     msg!("Portfolio initialized: manager={}, threshold={}%, interval={}s", 
          manager, rebalance_threshold, min_rebalance_interval);
     
+    emit!(PortfolioInitializedEvent::from_portfolio(portfolio, current_time));
+    
     Ok(())
 }
 
+#[event]
+pub struct PortfolioInitializedEvent {
+    pub manager: Pubkey,
+    pub rebalance_threshold: u8,
+    pub min_rebalance_interval: i64,
+    pub performance_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+impl PortfolioInitializedEvent {
+    pub fn from_portfolio(portfolio: &Portfolio, timestamp: i64) -> Self {
+        PortfolioInitializedEvent {
+            manager: portfolio.manager,
+            rebalance_threshold: portfolio.rebalance_threshold,
+            min_rebalance_interval: portfolio.min_rebalance_interval,
+            performance_fee_bps: portfolio.performance_fee_bps,
+            timestamp,
+        }
+    }
+}
+
 // Legacy initialize function for backward compatibility
 #[derive(Accounts)]
 pub struct Initialize {}
@@ -63,3 +117,70 @@ pub fn handler(ctx: Context<Initialize>) -> Result<()> {
     msg!("Legacy initialize called from: {:?}", ctx.program_id);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_invalid_threshold_diagnostic() {
+        let err = Portfolio::validate_rebalance_threshold(75).unwrap_err();
+        assert_eq!(err, crate::errors::RebalancerError::InvalidRebalanceThreshold.into());
+
+        // Line logged before the require! failure
+        assert_eq!(
+            out_of_range_log("rebalance threshold", 75u8, "1-50"),
+            "Invalid rebalance threshold: received 75, allowed 1-50"
+        );
+        assert!(Portfolio::validate_rebalance_threshold(25).is_ok());
+    }
+
+    #[test]
+    fn test_portfolio_initialized_event_decodes() {
+        let manager = Pubkey::new_unique();
+        let portfolio = Portfolio {
+            manager,
+            total_strategies: 0,
+            last_rebalance: 1_700_000_000,
+            portfolio_creation: 1_700_000_000,
+            performance_fee_bps: DEFAULT_PERFORMANCE_FEE_BPS,
+            ..test_fixtures::portfolio()
+        };
+        let event = PortfolioInitializedEvent::from_portfolio(&portfolio, portfolio.portfolio_creation);
+
+        let data = anchor_lang::Event::data(&event);
+        assert_eq!(&data[..8], PortfolioInitializedEvent::DISCRIMINATOR);
+        let decoded = PortfolioInitializedEvent::try_from_slice(&data[8..]).unwrap();
+
+        assert_eq!(decoded.manager, manager);
+        assert_eq!(decoded.rebalance_threshold, 25);
+        assert_eq!(decoded.min_rebalance_interval, 3600);
+        assert_eq!(decoded.performance_fee_bps, DEFAULT_PERFORMANCE_FEE_BPS);
+        assert_eq!(decoded.timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn test_creation_rate_limit() {
+        let mut limit = CreationRateLimit {
+            authority: Pubkey::new_unique(),
+            window_start: 0,
+            count: 0,
+            bump: 255,
+        };
+        let now = 1_000_000i64;
+
+        for _ in 0..MAX_PORTFOLIOS_PER_WINDOW {
+            limit.record_creation(now).unwrap();
+        }
+        assert_eq!(limit.count, MAX_PORTFOLIOS_PER_WINDOW);
+
+        // Next creation in the same window is rejected
+        assert!(limit.record_creation(now + 60).is_err());
+
+        // A new window resets the counter
+        limit.record_creation(now + PORTFOLIO_CREATION_WINDOW).unwrap();
+        assert_eq!(limit.count, 1);
+    }
+}
@@ -0,0 +1,153 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+#[derive(Accounts)]
+pub struct ManagerTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    pub manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptManagerTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    pub new_manager: Signer<'info>,
+}
+
+pub fn propose_manager_transfer(
+    ctx: Context<ManagerTransfer>,
+    new_manager: Pubkey,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+
+    portfolio.propose_manager_transfer(new_manager)?;
+
+    msg!("Manager transfer proposed: {} -> {}", portfolio.manager, new_manager);
+
+    emit!(ManagerTransferProposedEvent {
+        portfolio: portfolio.key(),
+        manager: portfolio.manager,
+        pending_manager: new_manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_manager_transfer(ctx: Context<ManagerTransfer>) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+
+    let cancelled = portfolio.cancel_manager_transfer()?;
+
+    msg!("Manager transfer to {} cancelled", cancelled);
+
+    emit!(ManagerTransferCancelledEvent {
+        portfolio: portfolio.key(),
+        manager: portfolio.manager,
+        cancelled_manager: cancelled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn accept_manager_transfer(ctx: Context<AcceptManagerTransfer>) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+
+    let previous = portfolio.accept_manager_transfer(&ctx.accounts.new_manager.key())?;
+
+    msg!("Manager transfer accepted: {} -> {}", previous, portfolio.manager);
+
+    emit!(ManagerTransferAcceptedEvent {
+        portfolio: portfolio.key(),
+        previous_manager: previous,
+        manager: portfolio.manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct ManagerTransferProposedEvent {
+    pub portfolio: Pubkey,
+    pub manager: Pubkey,
+    pub pending_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerTransferCancelledEvent {
+    pub portfolio: Pubkey,
+    pub manager: Pubkey,
+    pub cancelled_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerTransferAcceptedEvent {
+    pub portfolio: Pubkey,
+    pub previous_manager: Pubkey,
+    pub manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_cancelled_transfer_cannot_be_accepted() {
+        let mut portfolio = test_fixtures::portfolio();
+        let manager = portfolio.manager;
+        let proposed = Pubkey::new_unique();
+
+        // Nothing to cancel yet; self and default keys are not valid proposals
+        assert_eq!(portfolio.cancel_manager_transfer().unwrap_err(), RebalancerError::NoPendingManagerTransfer.into());
+        assert!(portfolio.propose_manager_transfer(manager).is_err());
+        assert!(portfolio.propose_manager_transfer(Pubkey::default()).is_err());
+
+        portfolio.propose_manager_transfer(proposed).unwrap();
+        assert_eq!(portfolio.pending_manager, proposed);
+
+        assert_eq!(portfolio.cancel_manager_transfer().unwrap(), proposed);
+        assert_eq!(portfolio.pending_manager, Pubkey::default());
+        assert_eq!(portfolio.cancel_manager_transfer().unwrap_err(), RebalancerError::NoPendingManagerTransfer.into());
+
+        // The former pending key can no longer take over
+        assert_eq!(
+            portfolio.accept_manager_transfer(&proposed).unwrap_err(),
+            RebalancerError::NoPendingManagerTransfer.into()
+        );
+        assert_eq!(portfolio.manager, manager);
+    }
+
+    #[test]
+    fn test_accepted_transfer_keeps_portfolio_address() {
+        let mut portfolio = test_fixtures::portfolio();
+        let (original, seed_manager) = (portfolio.manager, portfolio.seed_manager);
+        let proposed = Pubkey::new_unique();
+
+        portfolio.propose_manager_transfer(proposed).unwrap();
+        assert_eq!(portfolio.accept_manager_transfer(&Pubkey::new_unique()).unwrap_err(), RebalancerError::InvalidManager.into());
+
+        assert_eq!(portfolio.accept_manager_transfer(&proposed).unwrap(), original);
+        assert_eq!(portfolio.manager, proposed);
+        assert_eq!(portfolio.pending_manager, Pubkey::default());
+        assert_eq!(portfolio.seed_manager, seed_manager);
+    }
+}
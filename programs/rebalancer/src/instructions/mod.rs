@@ -4,6 +4,21 @@ pub mod update_performance;
 pub mod execute_ranking;
 pub mod extract_capital;
 pub mod redistribute_capital;
+pub mod update_positions;
+pub mod update_config;
+pub mod strategy_accounts;
+pub mod bulk_status;
+pub mod strategy_settings;
+pub mod repair_count;
+pub mod rebalance_status;
+pub mod validate_plan;
+pub mod treasury_whitelist;
+pub mod performance_fee;
+pub mod guardians;
+pub mod registry;
+pub mod deposit_queue;
+pub mod summary_pack;
+pub mod manager_transfer;
 
 pub use initialize::*;
 pub use register_strategy::*;
@@ -11,3 +26,18 @@ pub use update_performance::*;
 pub use execute_ranking::*;
 pub use extract_capital::*;
 pub use redistribute_capital::*;
+pub use update_positions::*;
+pub use update_config::*;
+pub use strategy_accounts::*;
+pub use bulk_status::*;
+pub use strategy_settings::*;
+pub use repair_count::*;
+pub use rebalance_status::*;
+pub use validate_plan::*;
+pub use treasury_whitelist::*;
+pub use performance_fee::*;
+pub use guardians::*;
+pub use registry::*;
+pub use deposit_queue::*;
+pub use summary_pack::*;
+pub use manager_transfer::*;
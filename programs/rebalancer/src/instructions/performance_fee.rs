@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use super::strategy_settings::ConfigureStrategy;
+
+pub fn collect_performance_fee(
+    ctx: Context<ConfigureStrategy>,
+    _strategy_id: Pubkey,
+) -> Result<()> {
+    let fee_bps = ctx.accounts.portfolio.performance_fee_bps;
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(!ctx.accounts.portfolio.is_paused(Clock::get()?.unix_timestamp), RebalancerError::EmergencyPauseActive);
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+
+    let fee = strategy.charge_performance_fee(fee_bps)?;
+
+    msg!("Performance fee collected: strategy={}, fee={}, high water mark={}",
+         strategy.strategy_id, fee, strategy.high_water_mark);
+
+    emit!(PerformanceFeeCollectedEvent {
+        portfolio: portfolio_key,
+        strategy_id: strategy.strategy_id,
+        fee,
+        high_water_mark: strategy.high_water_mark,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct PerformanceFeeCollectedEvent {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub fee: u64,
+    pub high_water_mark: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    const SOL: u64 = 1_000_000_000;
+
+    fn strategy_at_mark(high_water_mark: u64) -> Strategy {
+        Strategy {
+            current_balance: high_water_mark,
+            yield_rate: 800,
+            volatility_score: 1500,
+            performance_score: 5000,
+            smoothed_score: 5000,
+            total_deposits: high_water_mark,
+            high_water_mark,
+            ..test_fixtures::strategy()
+        }
+    }
+
+    #[test]
+    fn test_recovery_below_mark_pays_no_fee() {
+        let mut strategy = strategy_at_mark(10 * SOL);
+
+        // Drops to 7 SOL, recovers to 9.5 SOL: still under the 10 SOL peak
+        strategy.current_balance = 7 * SOL;
+        assert_eq!(strategy.charge_performance_fee(200).unwrap(), 0);
+        strategy.current_balance = 9 * SOL + SOL / 2;
+
+        assert_eq!(strategy.charge_performance_fee(200).unwrap(), 0);
+        assert_eq!(strategy.current_balance, 9 * SOL + SOL / 2);
+        assert_eq!(strategy.high_water_mark, 10 * SOL);
+    }
+
+    #[test]
+    fn test_fee_charged_on_excess_above_mark_only() {
+        let mut strategy = strategy_at_mark(10 * SOL);
+
+        // Drops then climbs to 12 SOL: only the 2 SOL above the peak is a gain
+        strategy.current_balance = 8 * SOL;
+        assert_eq!(strategy.charge_performance_fee(200).unwrap(), 0);
+        strategy.current_balance = 12 * SOL;
+
+        let fee = strategy.charge_performance_fee(200).unwrap();
+        assert_eq!(fee, 2 * SOL * 200 / 10000);
+        assert_eq!(strategy.current_balance, 12 * SOL - fee);
+        assert_eq!(strategy.high_water_mark, 12 * SOL - fee);
+
+        // Nothing further is due until a new peak is set
+        assert_eq!(strategy.charge_performance_fee(200).unwrap(), 0);
+    }
+}
@@ -0,0 +1,690 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::*;
+use super::strategy_accounts::*;
+use super::execute_ranking::ensure_unique_strategy_ids;
+
+#[derive(Accounts)]
+pub struct IsRebalanceable<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+// Read-only view: reports whether execute_ranking_cycle would pass its gating checks
+pub fn is_rebalanceable(ctx: Context<IsRebalanceable>) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let reason_code = portfolio.rebalance_eligibility(Clock::get()?.unix_timestamp);
+
+    msg!("Rebalance eligibility: portfolio={}, eligible={}, reason={}",
+         portfolio.key(), reason_code == REBALANCE_ELIGIBLE, reason_code);
+
+    emit!(RebalanceEligibilityEvent {
+        portfolio: portfolio.key(),
+        eligible: reason_code == REBALANCE_ELIGIBLE,
+        reason_code,
+        ranking_cycle_count: portfolio.ranking_cycle_count,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct RebalanceEligibilityEvent {
+    pub portfolio: Pubkey,
+    pub eligible: bool,
+    pub reason_code: u8,
+    pub ranking_cycle_count: u64,
+}
+
+#[derive(Accounts)]
+pub struct IsStale<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    // remaining_accounts: every Strategy PDA of this portfolio (total_strategies of them)
+}
+
+// Read-only view for cleanup cranks: the full strategy set must be supplied so
+// "no active strategies" is proven rather than assumed
+pub fn is_stale<'info>(ctx: Context<'_, '_, 'info, 'info, IsStale<'info>>) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let portfolio_key = portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let strategies = ctx.remaining_accounts
+        .iter()
+        .map(|account_info| load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id))
+        .collect::<Result<Vec<_>>>()?;
+    let strategy_refs: Vec<&Strategy> = strategies.iter().map(|strategy| &**strategy).collect();
+    let active_strategies = count_active_strategies(portfolio, &strategy_refs)?;
+
+    let age_seconds = current_time.saturating_sub(portfolio.last_rebalance);
+    let stale = portfolio.is_stale(current_time, active_strategies);
+
+    msg!("Stale check: portfolio={}, age={}s, active={}, stale={}",
+         portfolio_key, age_seconds, active_strategies, stale);
+
+    emit!(StalePortfolioEvent {
+        portfolio: portfolio_key,
+        last_rebalance: portfolio.last_rebalance,
+        age_seconds,
+        stale,
+    });
+
+    Ok(())
+}
+
+// The supplied set must be exactly the portfolio's strategies, each once;
+// repeating a paused strategy must not stand in for an active one
+pub fn count_active_strategies(portfolio: &Portfolio, strategies: &[&Strategy]) -> Result<u32> {
+    require!(
+        strategies.len() == portfolio.total_strategies as usize,
+        RebalancerError::StrategyCountMismatch
+    );
+    let strategy_ids: Vec<Pubkey> = strategies.iter().map(|strategy| strategy.strategy_id).collect();
+    ensure_unique_strategy_ids(&strategy_ids)?;
+
+    let mut active_strategies = 0u32;
+    for strategy in strategies {
+        if strategy.status == StrategyStatus::Active {
+            active_strategies = active_strategies
+                .checked_add(1)
+                .ok_or(RebalancerError::MathOverflow)?;
+        }
+    }
+    Ok(active_strategies)
+}
+
+#[event]
+pub struct StalePortfolioEvent {
+    pub portfolio: Pubkey,
+    pub last_rebalance: i64,
+    pub age_seconds: i64,
+    pub stale: bool,
+}
+
+#[derive(Accounts)]
+pub struct EmitStaleness<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    // remaining_accounts: Strategy PDAs of this portfolio to report on
+}
+
+// Read-only view for monitoring: per-strategy metric age plus a stale count
+pub fn emit_staleness<'info>(
+    ctx: Context<'_, '_, 'info, 'info, EmitStaleness<'info>>,
+    staleness_threshold: i64,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(staleness_threshold > 0, RebalancerError::InvalidRebalanceInterval);
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut stale_count = 0u32;
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+        let seconds_since_update = strategy.seconds_since_update(current_time);
+
+        if seconds_since_update > staleness_threshold {
+            stale_count = stale_count
+                .checked_add(1)
+                .ok_or(RebalancerError::MathOverflow)?;
+        }
+
+        emit!(StalenessEvent {
+            strategy_id: strategy.strategy_id,
+            seconds_since_update,
+        });
+    }
+
+    msg!("Staleness check: portfolio={}, {} of {} strategies older than {}s",
+         portfolio_key, stale_count, ctx.remaining_accounts.len(), staleness_threshold);
+
+    emit!(StalenessSummaryEvent {
+        portfolio: portfolio_key,
+        strategies_checked: ctx.remaining_accounts.len() as u32,
+        stale_count,
+        staleness_threshold,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct StalenessEvent {
+    pub strategy_id: Pubkey,
+    pub seconds_since_update: i64,
+}
+
+#[event]
+pub struct StalenessSummaryEvent {
+    pub portfolio: Pubkey,
+    pub strategies_checked: u32,
+    pub stale_count: u32,
+    pub staleness_threshold: i64,
+}
+
+#[derive(Accounts)]
+pub struct EmitStrategySnapshots<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    // remaining_accounts: up to MAX_STRATEGY_BATCH Strategy PDAs of this portfolio
+}
+
+// Read-only view for dashboards: one event carrying every passed strategy's summary
+pub fn emit_strategy_snapshots<'info>(
+    ctx: Context<'_, '_, 'info, 'info, EmitStrategySnapshots<'info>>,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+
+    // Cap keeps the event well inside the program log limit (~62 bytes per summary)
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut snapshots = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+        snapshots.push(StrategySummary::from_strategy(&strategy));
+    }
+
+    msg!("Strategy snapshots: portfolio={}, count={}", portfolio_key, snapshots.len());
+
+    emit!(StrategySnapshotsEvent {
+        portfolio: portfolio_key,
+        snapshots,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct StrategySummary {
+    pub strategy_id: Pubkey,
+    pub performance_score: u64,
+    pub percentile_rank: u8,
+    pub current_balance: u64,
+    pub volatility_score: u32,
+    pub status: StrategyStatus,
+    pub display_apy_bps: u64,  // Estimate for UIs only - see score_to_display_apy
+}
+
+impl StrategySummary {
+    pub fn from_strategy(strategy: &Strategy) -> Self {
+        StrategySummary {
+            strategy_id: strategy.strategy_id,
+            performance_score: strategy.performance_score,
+            percentile_rank: strategy.percentile_rank,
+            current_balance: strategy.current_balance,
+            volatility_score: strategy.volatility_score,
+            status: strategy.status,
+            display_apy_bps: score_to_display_apy(strategy.performance_score, strategy.yield_rate),
+        }
+    }
+}
+
+// DISPLAY APY (ESTIMATE)
+// Turns the opaque 0-10000 composite into an APY-style number users can read: the
+// reported yield_rate (bps), discounted by how much of the composite the strategy
+// earns. A perfect score shows the full yield, a zero score DISPLAY_APY_SCORE_FLOOR_BPS
+// of it. Display only - not a forecast, and never an input to ranking or allocation.
+pub fn score_to_display_apy(score: u64, yield_rate: u64) -> u64 {
+    let floor = DISPLAY_APY_SCORE_FLOOR_BPS as u128;
+    let credit_bps = floor + (10000 - floor) * score.min(10000) as u128 / 10000;
+    (yield_rate as u128 * credit_bps / 10000) as u64
+}
+
+#[event]
+pub struct StrategySnapshotsEvent {
+    pub portfolio: Pubkey,
+    pub snapshots: Vec<StrategySummary>,
+    pub timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct PortfolioSummary<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    // remaining_accounts: up to MAX_STRATEGY_BATCH Strategy PDAs of this portfolio
+}
+
+// Read-only view: portfolio-wide aggregates over the passed strategies
+pub fn portfolio_summary<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PortfolioSummary<'info>>,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let portfolio_key = portfolio.key();
+
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let mut aggregates = PortfolioAggregates::default();
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+        aggregates.add(&strategy)?;
+    }
+
+    let event = PortfolioSummaryEvent::from_aggregates(
+        portfolio_key,
+        portfolio,
+        &aggregates,
+        Clock::get()?.unix_timestamp,
+    );
+
+    msg!("Portfolio summary: portfolio={}, active={}/{}, avg_score={}, avg_volatility={}, aum={}, threshold={}, fee={}bps",
+         portfolio_key, event.active_strategies, event.strategies_counted, event.average_score,
+         event.average_volatility, event.total_aum, event.dynamic_threshold, event.performance_fee_bps);
+
+    emit!(event);
+
+    Ok(())
+}
+
+// Running totals for portfolio_summary; every passed strategy counts toward AUM,
+// only active ones toward the averages
+#[derive(Default, Debug)]
+pub struct PortfolioAggregates {
+    pub strategies_counted: u32,
+    pub active_strategies: u32,
+    pub score_sum: u64,
+    pub volatility_sum: u64,
+    pub total_aum: u64,
+}
+
+impl PortfolioAggregates {
+    pub fn add(&mut self, strategy: &Strategy) -> Result<()> {
+        self.strategies_counted = self.strategies_counted
+            .checked_add(1)
+            .ok_or(RebalancerError::MathOverflow)?;
+        self.total_aum = self.total_aum
+            .checked_add(strategy.current_balance)
+            .ok_or(RebalancerError::BalanceOverflow)?;
+
+        if strategy.status != StrategyStatus::Active {
+            return Ok(());
+        }
+
+        self.active_strategies = self.active_strategies
+            .checked_add(1)
+            .ok_or(RebalancerError::MathOverflow)?;
+        self.score_sum = self.score_sum
+            .checked_add(strategy.performance_score)
+            .ok_or(RebalancerError::MathOverflow)?;
+        self.volatility_sum = self.volatility_sum
+            .checked_add(strategy.volatility_score as u64)
+            .ok_or(RebalancerError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn average_score(&self) -> u64 {
+        self.score_sum.checked_div(self.active_strategies as u64).unwrap_or(0)
+    }
+
+    // Volatility scores are at most 10000, so the average always fits in u32
+    pub fn average_volatility(&self) -> u32 {
+        self.volatility_sum.checked_div(self.active_strategies as u64).unwrap_or(0) as u32
+    }
+}
+
+#[event]
+pub struct PortfolioSummaryEvent {
+    pub portfolio: Pubkey,
+    pub strategies_counted: u32,
+    pub active_strategies: u32,
+    pub average_score: u64,
+    pub average_volatility: u32,
+    pub total_aum: u64,
+    pub base_currency: [u8; 8],
+    pub dynamic_threshold: u8,
+    pub performance_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+impl PortfolioSummaryEvent {
+    pub fn from_aggregates(
+        portfolio_key: Pubkey,
+        portfolio: &Portfolio,
+        aggregates: &PortfolioAggregates,
+        timestamp: i64,
+    ) -> Self {
+        // Before the first batch ranking no dynamic threshold has been recorded
+        let dynamic_threshold = match portfolio.last_dynamic_threshold() {
+            0 => portfolio.rebalance_threshold,
+            threshold => threshold,
+        };
+
+        PortfolioSummaryEvent {
+            portfolio: portfolio_key,
+            strategies_counted: aggregates.strategies_counted,
+            active_strategies: aggregates.active_strategies,
+            average_score: aggregates.average_score(),
+            average_volatility: aggregates.average_volatility(),
+            total_aum: aggregates.total_aum,
+            base_currency: portfolio.base_currency,
+            dynamic_threshold,
+            performance_fee_bps: portfolio.performance_fee_bps,
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn eligible_portfolio() -> Portfolio {
+        test_fixtures::portfolio()
+    }
+
+    #[test]
+    fn test_rebalance_eligibility_reasons() {
+        let now = 10_000i64;
+
+        let portfolio = eligible_portfolio();
+        assert_eq!(portfolio.rebalance_eligibility(now), REBALANCE_ELIGIBLE);
+        assert!(portfolio.require_rebalanceable(now).is_ok());
+
+        let paused = Portfolio { emergency_pause: true, ..eligible_portfolio() };
+        assert_eq!(paused.rebalance_eligibility(now), REBALANCE_BLOCKED_PAUSED);
+
+        let recent = Portfolio { last_rebalance: now - 60, ..eligible_portfolio() };
+        assert_eq!(recent.rebalance_eligibility(now), REBALANCE_BLOCKED_INTERVAL);
+
+        let sparse = Portfolio { total_strategies: 1, ..eligible_portfolio() };
+        assert_eq!(sparse.rebalance_eligibility(now), REBALANCE_BLOCKED_TOO_FEW_STRATEGIES);
+        assert!(sparse.require_rebalanceable(now).is_err());
+    }
+
+    #[test]
+    fn test_time_boxed_pause_expires() {
+        let now = 10_000i64;
+        let mut portfolio = eligible_portfolio();
+        portfolio.config.pause_grace = 3600;
+
+        // Pause with a one-hour grace blocks rebalancing until it lapses
+        portfolio.set_emergency_pause(true, now);
+        assert_eq!(portfolio.pause_expires_at(), now + 3600);
+        assert!(portfolio.is_paused(now + 3599));
+        assert!(!portfolio.can_rebalance(now + 3599));
+        assert_eq!(portfolio.rebalance_eligibility(now + 3599), REBALANCE_BLOCKED_PAUSED);
+
+        // Past the expiry rebalancing resumes without an explicit unpause
+        assert!(portfolio.emergency_pause);
+        assert!(!portfolio.is_paused(now + 3600));
+        assert!(portfolio.can_rebalance(now + 3600));
+        assert!(portfolio.require_rebalanceable(now + 3600).is_ok());
+
+        // Zero grace means indefinite
+        portfolio.config.pause_grace = 0;
+        portfolio.set_emergency_pause(true, now);
+        assert_eq!(portfolio.pause_expires_at(), 0);
+        assert!(portfolio.is_paused(i64::MAX));
+
+        // Lifting the pause clears the expiry
+        portfolio.set_emergency_pause(false, now);
+        assert!(!portfolio.is_paused(now));
+    }
+
+    #[test]
+    fn test_stale_portfolio_detection() {
+        let ninety_days = DEFAULT_STALE_WINDOW as i64;
+        let abandoned = Portfolio { last_rebalance: 0, ..eligible_portfolio() };
+
+        // Old last_rebalance with nothing active is stale
+        assert!(abandoned.is_stale(ninety_days + 1, 0));
+
+        // Active strategies or a recent rebalance keep it alive
+        assert!(!abandoned.is_stale(ninety_days + 1, 1));
+        assert!(!abandoned.is_stale(ninety_days - 1, 0));
+    }
+
+    #[test]
+    fn test_stale_check_rejects_repeated_strategy() {
+        let portfolio = Portfolio { total_strategies: 3, ..eligible_portfolio() };
+        let active = test_fixtures::strategy();
+        let paused = Strategy { status: StrategyStatus::Paused, ..test_fixtures::strategy() };
+        let other_paused = Strategy { status: StrategyStatus::Paused, ..test_fixtures::strategy() };
+
+        // The full set is counted as supplied
+        assert_eq!(count_active_strategies(&portfolio, &[&active, &paused, &other_paused]).unwrap(), 1);
+
+        // One paused strategy passed three times cannot hide the active one
+        let err = count_active_strategies(&portfolio, &[&paused, &paused, &paused]).unwrap_err();
+        assert_eq!(err, RebalancerError::DuplicateStrategy.into());
+
+        // Nor can the set be short
+        let err = count_active_strategies(&portfolio, &[&paused, &other_paused]).unwrap_err();
+        assert_eq!(err, RebalancerError::StrategyCountMismatch.into());
+    }
+
+    fn strategy_updated_at(updated_at: i64) -> Strategy {
+        Strategy {
+            current_balance: 1_000_000_000,
+            yield_rate: 800,
+            volatility_score: 1500,
+            performance_score: 5000,
+            smoothed_score: 5000,
+            last_updated: updated_at,
+            last_performance_update: updated_at,
+            ..test_fixtures::strategy()
+        }
+    }
+
+    #[test]
+    fn test_strategy_metric_age() {
+        let now = 1_000_000i64;
+        let fresh = strategy_updated_at(now - 120);
+        let stale = strategy_updated_at(now - 3 * 86400);
+
+        assert_eq!(fresh.seconds_since_update(now), 120);
+        assert_eq!(stale.seconds_since_update(now), 259_200);
+
+        let threshold = 86400;
+        let stale_count = [&fresh, &stale]
+            .iter()
+            .filter(|s| s.seconds_since_update(now) > threshold)
+            .count();
+        assert_eq!(stale_count, 1);
+
+        // A ranking stamps last_updated but does not make the metrics any younger
+        let ranked = Strategy { last_updated: now, ..strategy_updated_at(now - 3 * 86400) };
+        assert_eq!(ranked.seconds_since_update(now), 259_200);
+
+        // Clock behind the update timestamp reports zero, not a negative age
+        assert_eq!(fresh.seconds_since_update(now - 600), 0);
+    }
+
+    #[test]
+    fn test_three_strategy_snapshots_in_one_event() {
+        let mut strategies = [
+            strategy_updated_at(0),
+            strategy_updated_at(0),
+            strategy_updated_at(0),
+        ];
+        strategies[1].percentile_rank = 90;
+        strategies[2].status = StrategyStatus::Paused;
+
+        let event = StrategySnapshotsEvent {
+            portfolio: Pubkey::new_unique(),
+            snapshots: strategies.iter().map(StrategySummary::from_strategy).collect(),
+            timestamp: 0,
+        };
+
+        assert_eq!(event.snapshots.len(), 3);
+        for (summary, strategy) in event.snapshots.iter().zip(strategies.iter()) {
+            assert_eq!(summary.strategy_id, strategy.strategy_id);
+            assert_eq!(summary.percentile_rank, strategy.percentile_rank);
+            assert_eq!(summary.status, strategy.status);
+        }
+
+        // A full batch still fits comfortably in one log line
+        let full = StrategySnapshotsEvent {
+            snapshots: vec![event.snapshots[0].clone(); MAX_STRATEGY_BATCH],
+            ..event
+        };
+        assert!(full.try_to_vec().unwrap().len() < 1024);
+    }
+
+    #[test]
+    fn test_portfolio_summary_averages_active_strategies() {
+        let mut strategies = [
+            strategy_updated_at(0),
+            strategy_updated_at(0),
+            strategy_updated_at(0),
+        ];
+        strategies[0].performance_score = 7000;
+        strategies[0].volatility_score = 1000;
+        strategies[1].performance_score = 4000;
+        strategies[1].volatility_score = 3000;
+        strategies[1].current_balance = 3_000_000_000;
+        strategies[2].performance_score = 100;
+        strategies[2].volatility_score = 9000;
+        strategies[2].status = StrategyStatus::Deprecated;
+
+        let mut aggregates = PortfolioAggregates::default();
+        for strategy in strategies.iter() {
+            aggregates.add(strategy).unwrap();
+        }
+
+        // Deprecated strategy still holds capital but is left out of the averages
+        assert_eq!(aggregates.strategies_counted, 3);
+        assert_eq!(aggregates.active_strategies, 2);
+        assert_eq!(aggregates.average_score(), 5500);
+        assert_eq!(aggregates.average_volatility(), 2000);
+        assert_eq!(aggregates.total_aum, 5_000_000_000);
+
+        // No active strategies reports zero averages rather than dividing by zero
+        let mut inactive = PortfolioAggregates::default();
+        inactive.add(&strategies[2]).unwrap();
+        assert_eq!(inactive.average_score(), 0);
+        assert_eq!(inactive.average_volatility(), 0);
+
+        let mut overflowing = PortfolioAggregates { total_aum: u64::MAX, ..PortfolioAggregates::default() };
+        assert!(overflowing.add(&strategies[0]).is_err());
+    }
+
+    #[test]
+    fn test_summary_reports_current_performance_fee() {
+        use crate::instructions::update_config::PortfolioConfigUpdatedEvent;
+
+        let mut portfolio = eligible_portfolio();
+        let portfolio_key = Pubkey::new_unique();
+        let mut aggregates = PortfolioAggregates::default();
+        aggregates.add(&strategy_updated_at(0)).unwrap();
+
+        let summary = PortfolioSummaryEvent::from_aggregates(portfolio_key, &portfolio, &aggregates, 0);
+        assert_eq!(summary.performance_fee_bps, 200);
+        assert_eq!(summary.dynamic_threshold, 25); // Falls back before any batch ranking
+
+        // A fee change shows up in both the config event and the next summary
+        Portfolio::validate_performance_fee(750).unwrap();
+        portfolio.performance_fee_bps = 750;
+        let updated = PortfolioConfigUpdatedEvent::from_portfolio(portfolio_key, &portfolio, 60);
+        assert_eq!(updated.performance_fee_bps, 750);
+
+        let summary = PortfolioSummaryEvent::from_aggregates(portfolio_key, &portfolio, &aggregates, 120);
+        assert_eq!(summary.performance_fee_bps, 750);
+
+        // Clients decode the fee straight from the event payload
+        let data = anchor_lang::Event::data(&summary);
+        assert_eq!(&data[..8], PortfolioSummaryEvent::DISCRIMINATOR);
+        let decoded = PortfolioSummaryEvent::try_from_slice(&data[8..]).unwrap();
+        assert_eq!(decoded.performance_fee_bps, 750);
+    }
+
+    #[test]
+    fn test_reserved_fields_do_not_overlap() {
+        use crate::state::reserved_layout::*;
+
+        let mut portfolio = eligible_portfolio();
+        portfolio.set_last_dynamic_threshold(37);
+        portfolio.set_last_ranked_at(-1);
+        portfolio.set_last_ranked_at(1_700_000_000);
+
+        // Writing one field leaves the other intact
+        assert_eq!(portfolio.last_dynamic_threshold(), 37);
+        assert_eq!(portfolio.last_ranked_at(), 1_700_000_000);
+        assert!(portfolio.reserved[PORTFOLIO_LAST_RANKED_AT.end..].iter().all(|&b| b == 0));
+
+        let mut strategy = strategy_updated_at(0);
+        strategy.set_rank_stale(true);
+        assert!(strategy.rank_stale());
+        strategy.set_rank_stale(false);
+        assert!(!strategy.rank_stale());
+    }
+
+    #[test]
+    fn test_pause_reported_before_other_reasons() {
+        let blocked_everywhere = Portfolio {
+            emergency_pause: true,
+            last_rebalance: 10_000,
+            total_strategies: 0,
+            ..eligible_portfolio()
+        };
+        assert_eq!(blocked_everywhere.rebalance_eligibility(10_000), REBALANCE_BLOCKED_PAUSED);
+    }
+
+    #[test]
+    fn test_base_currency_propagates_to_summary() {
+        let usdc = *b"USDC\0\0\0\0";
+        Portfolio::validate_base_currency(&usdc).unwrap();
+        let portfolio = Portfolio { base_currency: usdc, ..eligible_portfolio() };
+
+        let mut aggregates = PortfolioAggregates::default();
+        aggregates.add(&strategy_updated_at(0)).unwrap();
+        let summary = PortfolioSummaryEvent::from_aggregates(Pubkey::new_unique(), &portfolio, &aggregates, 0);
+
+        let data = anchor_lang::Event::data(&summary);
+        let decoded = PortfolioSummaryEvent::try_from_slice(&data[8..]).unwrap();
+        assert_eq!(&decoded.base_currency, b"USDC\0\0\0\0");
+        assert_eq!(decoded.total_aum, aggregates.total_aum);
+
+        // Empty, lowercase, and gapped labels are rejected
+        assert!(Portfolio::validate_base_currency(&DEFAULT_BASE_CURRENCY).is_ok());
+        assert!(Portfolio::validate_base_currency(&[0u8; 8]).is_err());
+        assert!(Portfolio::validate_base_currency(b"usdc\0\0\0\0").is_err());
+        assert!(Portfolio::validate_base_currency(b"US\0DC\0\0\0").is_err());
+        assert!(Portfolio::validate_base_currency(b"JITOSOL1").is_ok());
+    }
+
+    #[test]
+    fn test_score_to_display_apy() {
+        // 8% reported yield across the score range: 4% at the bottom, the full 8% at the top
+        assert_eq!(score_to_display_apy(0, 800), 400);
+        assert_eq!(score_to_display_apy(5000, 800), 600);
+        assert_eq!(score_to_display_apy(7500, 800), 700);
+        assert_eq!(score_to_display_apy(10000, 800), 800);
+
+        // Never above the reported yield, even for an out-of-range score
+        assert_eq!(score_to_display_apy(20000, 800), 800);
+        assert_eq!(score_to_display_apy(9000, 0), 0);
+        assert_eq!(score_to_display_apy(10000, 50000), 50000);
+
+        let mut strategy = strategy_updated_at(0);
+        strategy.performance_score = 5000;
+        strategy.yield_rate = 1200;
+        assert_eq!(StrategySummary::from_strategy(&strategy).display_apy_bps, 900);
+    }
+}
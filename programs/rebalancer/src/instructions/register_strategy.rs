@@ -1,13 +1,16 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
 use crate::state::*;
 use crate::errors::*;
+use crate::instructions::update_performance::apply_balance_change;
+use crate::instructions::deposit_queue::deposit_must_queue;
 
 #[derive(Accounts)]
 #[instruction(strategy_id: Pubkey, protocol_type: ProtocolType, initial_balance: u64)]
 pub struct RegisterStrategy<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
         bump = portfolio.bump,
         has_one = manager @ RebalancerError::InvalidManager
     )]
@@ -22,6 +25,22 @@ pub struct RegisterStrategy<'info> {
     )]
     pub strategy: Account<'info, Strategy>,
     
+    /// Strategy capital vault; required when the portfolio enforces funded registration
+    #[account(
+        mut,
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: Option<SystemAccount<'info>>,
+    
+    /// Registry page the new strategy id is appended to; every registration is listed
+    #[account(
+        mut,
+        seeds = [b"registry", portfolio.key().as_ref(), &registry.page.to_le_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StrategyRegistry>,
+    
     #[account(mut)]
     pub manager: Signer<'info>,
     
@@ -39,7 +58,7 @@ pub fn register_strategy(
     let current_time = Clock::get()?.unix_timestamp;
     
     // COMPREHENSIVE SECURITY VALIDATIONS
-    require!(!portfolio.emergency_pause, RebalancerError::EmergencyPauseActive);
+    require!(!portfolio.is_paused(current_time), RebalancerError::EmergencyPauseActive);
     require!(strategy_id != Pubkey::default(), RebalancerError::InvalidProtocolType);
     require!(initial_balance > 0, RebalancerError::InsufficientBalance);
     Strategy::validate_balance_update(initial_balance)?;
@@ -47,30 +66,563 @@ pub fn register_strategy(
     // PROTOCOL-SPECIFIC VALIDATION
     protocol_type.validate()?;
     protocol_type.validate_balance_constraints(initial_balance)?;
+    portfolio.config.check_reward_multiplier(&protocol_type)?;
+    let (new_total_aum, over_cap) = portfolio.check_aum_deposit(initial_balance)?;
+    
+    // FUNDED REGISTRATION: MOVE REAL LAMPORTS INTO THE STRATEGY VAULT
+    if portfolio.config.require_funded_registration {
+        let vault = ctx.accounts.vault.as_ref().ok_or(RebalancerError::VaultRequired)?;
+        let balance_before = vault.lamports();
+        
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.manager.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            initial_balance,
+        )?;
+        
+        verify_vault_funding(balance_before, vault.lamports(), initial_balance)?;
+        msg!("Vault {} funded with {} lamports", vault.key(), initial_balance);
+    }
     
     // STRATEGY INITIALIZATION WITH SAFE DEFAULTS
     strategy.strategy_id = strategy_id;
     strategy.protocol_type = protocol_type;
     strategy.current_balance = initial_balance;
     strategy.yield_rate = 0; // Will be updated by performance tracking
-    strategy.volatility_score = 5000; // Start with moderate risk (50%)
+    strategy.volatility_score = protocol_type.default_volatility(); // Protocol-specific risk prior
     strategy.performance_score = 0; // Calculated after first performance update
+    strategy.smoothed_score = 0; // Seeded from the first performance update
     strategy.percentile_rank = 50; // Start at median
     strategy.last_updated = current_time;
+    strategy.last_performance_update = current_time;
     strategy.status = StrategyStatus::Active;
     strategy.total_deposits = initial_balance;
     strategy.total_withdrawals = 0;
     strategy.creation_time = current_time;
+    strategy.target_weight_bps = 0; // Set via set_target_weight for TargetWeight mode
+    strategy.quote_mint = Pubkey::default(); // Lamport-denominated until set_quote_currency
+    strategy.quote_decimals = 9;
     strategy.bump = ctx.bumps.strategy;
-    strategy.reserved = [0u8; 23];
+    strategy.yield_rate_signed = 0; // Non-negative until a loss is reported
+    strategy.pinned = false;
+    strategy.reinvest_rewards = false; // Rewards held in pending_rewards until enabled
+    strategy.pending_rewards = 0;
+    strategy.awaiting_recovery = false;
+    strategy.high_water_mark = initial_balance; // Fees apply to gains above the starting capital
+    strategy.extraction_cooldown_until = 0;
+    strategy.scoring_epoch = portfolio.scoring_epoch;
+    strategy.reserved = [0u8; 4];
     
     // UPDATE PORTFOLIO COUNTERS WITH OVERFLOW PROTECTION
     portfolio.total_strategies = portfolio.total_strategies
         .checked_add(1)
         .ok_or(RebalancerError::MathOverflow)?;
+    portfolio.total_aum = new_total_aum;
+    if over_cap {
+        emit_aum_cap_warning(portfolio, current_time);
+    }
+    
+    // DETERMINISTIC LISTING
+    let registry = &mut ctx.accounts.registry;
+    registry.append(strategy_id)?;
+    msg!("Strategy {} listed in registry page {}", strategy_id, registry.page);
     
     msg!("Strategy registered: ID={}, Protocol={}, Balance={}", 
          strategy_id, protocol_type.get_protocol_name(), initial_balance);
     
+    emit!(StrategyRegisteredEvent::new(
+        portfolio.key(),
+        strategy_id,
+        &protocol_type,
+        initial_balance,
+        current_time,
+    ));
+    
+    Ok(())
+}
+
+#[event]
+pub struct StrategyRegisteredEvent {
+    pub strategy_id: Pubkey,
+    pub protocol_kind: u8,      // ProtocolType::discriminant
+    pub initial_balance: u64,
+    pub portfolio: Pubkey,
+    pub timestamp: i64,
+}
+
+impl StrategyRegisteredEvent {
+    pub fn new(
+        portfolio: Pubkey,
+        strategy_id: Pubkey,
+        protocol_type: &ProtocolType,
+        initial_balance: u64,
+        timestamp: i64,
+    ) -> Self {
+        StrategyRegisteredEvent {
+            strategy_id,
+            protocol_kind: protocol_type.discriminant(),
+            initial_balance,
+            portfolio,
+            timestamp,
+        }
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct DepositToStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    /// Strategy capital vault; required when the portfolio enforces funded registration
+    #[account(
+        mut,
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: Option<SystemAccount<'info>>,
+    
+    /// Pending deposits; receives the deposit when the strategy is at its cap
+    #[account(
+        mut,
+        seeds = [b"deposit_queue", strategy.key().as_ref()],
+        bump = deposit_queue.bump
+    )]
+    pub deposit_queue: Option<Account<'info, DepositQueue>>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn deposit_to_strategy(
+    ctx: Context<DepositToStrategy>,
+    _strategy_id: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let portfolio = &ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    
+    require!(!portfolio.is_paused(current_time), RebalancerError::EmergencyPauseActive);
+    require!(strategy.status == StrategyStatus::Active, RebalancerError::StrategyNotFound);
+    require!(amount > 0, RebalancerError::InsufficientBalance);
+    
+    // STRATEGY CAP: QUEUE INSTEAD OF FAILING; EARLIER INTENTS KEEP PRIORITY
+    let at_cap = portfolio.config.exceeds_strategy_cap(strategy.current_balance.saturating_add(amount));
+    if deposit_must_queue(strategy, at_cap, ctx.accounts.deposit_queue.as_deref())? {
+        let queue = ctx.accounts.deposit_queue.as_mut().ok_or(RebalancerError::StrategyAtCapacity)?;
+        queue.enqueue(amount, portfolio.config.strategy_deposit_cap)?;
+        strategy.set_deposits_queued(true);
+        
+        msg!("Strategy {} at capacity; queued deposit of {} lamports ({} pending)",
+             strategy.strategy_id, amount, queue.count);
+        return Ok(());
+    }
+    
+    credit_deposit(
+        &mut ctx.accounts.portfolio,
+        &mut ctx.accounts.strategy,
+        ctx.accounts.vault.as_ref(),
+        &ctx.accounts.manager,
+        &ctx.accounts.system_program,
+        amount,
+        current_time,
+    )
+}
+
+// Moves a deposit into the strategy: AUM cap, vault funding when enforced, rescoring
+pub fn credit_deposit<'info>(
+    portfolio: &mut Account<'info, Portfolio>,
+    strategy: &mut Account<'info, Strategy>,
+    vault: Option<&SystemAccount<'info>>,
+    manager: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    // AUM CAP: HARD CAPS REJECT, SOFT CAPS WARN
+    let (new_total_aum, over_cap) = portfolio.check_aum_deposit(amount)?;
+    let new_balance = strategy.current_balance
+        .checked_add(amount)
+        .ok_or(RebalancerError::BalanceOverflow)?;
+    Strategy::validate_balance_update(new_balance)?;
+    
+    if portfolio.config.require_funded_registration {
+        let vault = vault.ok_or(RebalancerError::VaultRequired)?;
+        let balance_before = vault.lamports();
+        
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: manager.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        verify_vault_funding(balance_before, vault.lamports(), amount)?;
+    }
+    
+    apply_balance_change(strategy, new_balance, &portfolio.config)?;
+    strategy.total_deposits = strategy.total_deposits
+        .checked_add(amount)
+        .ok_or(RebalancerError::BalanceOverflow)?;
+    portfolio.total_aum = new_total_aum;
+    
+    msg!("Deposited {} lamports into strategy {} (portfolio AUM: {})",
+         amount, strategy.strategy_id, new_total_aum);
+    
+    if over_cap {
+        emit_aum_cap_warning(portfolio, current_time);
+    }
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct WithdrawFromStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    /// Strategy capital vault; required when the portfolio enforces funded registration
+    #[account(
+        mut,
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: Option<SystemAccount<'info>>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn withdraw_from_strategy(
+    ctx: Context<WithdrawFromStrategy>,
+    _strategy_id: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    
+    require!(!portfolio.is_paused(Clock::get()?.unix_timestamp), RebalancerError::EmergencyPauseActive);
+    require!(strategy.status == StrategyStatus::Active, RebalancerError::StrategyNotFound);
+    require!(amount > 0, RebalancerError::InsufficientBalance);
+    
+    let new_balance = strategy.current_balance
+        .checked_sub(amount)
+        .ok_or(RebalancerError::InsufficientBalance)?;
+    
+    if portfolio.config.require_funded_registration {
+        let vault = ctx.accounts.vault.as_ref().ok_or(RebalancerError::VaultRequired)?;
+        let vault_bump = ctx.bumps.vault.ok_or(RebalancerError::VaultRequired)?;
+        let strategy_key = strategy.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", strategy_key.as_ref(), &[vault_bump]];
+        verify_vault_remainder(vault.lamports(), amount, &Rent::get()?)?;
+        
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: vault.to_account_info(),
+                    to: ctx.accounts.manager.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            amount,
+        )?;
+    }
+    
+    // PARTIAL WITHDRAWAL RESCORES THE STRATEGY
+    apply_balance_change(strategy, new_balance, &portfolio.config)?;
+    strategy.total_withdrawals = strategy.total_withdrawals
+        .checked_add(amount)
+        .ok_or(RebalancerError::BalanceOverflow)?;
+    portfolio.total_aum = portfolio.total_aum.saturating_sub(amount);
+    
+    msg!("Withdrew {} lamports from strategy {} (balance: {}, score: {}, portfolio AUM: {})",
+         amount, strategy.strategy_id, new_balance, strategy.performance_score, portfolio.total_aum);
+    
+    Ok(())
+}
+
+pub(crate) fn emit_aum_cap_warning(portfolio: &Account<Portfolio>, timestamp: i64) {
+    msg!("AUM cap exceeded (soft): {} > {}", portfolio.total_aum, portfolio.config.target_aum_cap);
+    
+    emit!(AumCapWarningEvent {
+        portfolio: portfolio.key(),
+        total_aum: portfolio.total_aum,
+        target_aum_cap: portfolio.config.target_aum_cap,
+        base_currency: portfolio.base_currency,
+        timestamp,
+    });
+}
+
+#[event]
+pub struct AumCapWarningEvent {
+    pub portfolio: Pubkey,
+    pub total_aum: u64,
+    pub target_aum_cap: u64,
+    pub base_currency: [u8; 8],
+    pub timestamp: i64,
+}
+
+// Vault must have received exactly the declared initial balance
+pub fn verify_vault_funding(balance_before: u64, balance_after: u64, expected: u64) -> Result<()> {
+    let received = balance_after
+        .checked_sub(balance_before)
+        .ok_or(RebalancerError::FundingMismatch)?;
+    require!(received == expected, RebalancerError::FundingMismatch);
+    Ok(())
+}
+
+// A partial withdrawal must leave the vault rent-exempt; the runtime rejects
+// the transfer otherwise. Draining it to zero is allowed.
+pub fn verify_vault_remainder(vault_lamports: u64, amount: u64, rent: &Rent) -> Result<()> {
+    let remainder = vault_lamports
+        .checked_sub(amount)
+        .ok_or(RebalancerError::InsufficientBalance)?;
+    require!(
+        remainder == 0 || remainder >= rent.minimum_balance(0),
+        RebalancerError::VaultBelowRentExempt
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    
+    #[test]
+    fn test_protocol_default_volatility() {
+        let yield_farming = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 300,
+        };
+        let liquid_staking = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        
+        // Fresh farms carry a higher risk prior than fresh staking positions
+        assert!(yield_farming.default_volatility() > liquid_staking.default_volatility());
+        
+        // Priors must be valid volatility scores
+        Strategy::validate_volatility_score(yield_farming.default_volatility()).unwrap();
+        Strategy::validate_volatility_score(liquid_staking.default_volatility()).unwrap();
+    }
+    
+    #[test]
+    fn test_protocol_discriminant_round_trip() {
+        let protocols = [
+            ProtocolType::StableLending {
+                pool_id: Pubkey::new_unique(),
+                utilization: 5000,
+                reserve_address: Pubkey::new_unique(),
+            },
+            ProtocolType::YieldFarming {
+                pair_id: Pubkey::new_unique(),
+                reward_multiplier: 2,
+                token_a_mint: Pubkey::new_unique(),
+                token_b_mint: Pubkey::new_unique(),
+                fee_tier: 300,
+            },
+            ProtocolType::LiquidStaking {
+                validator_id: Pubkey::new_unique(),
+                commission: 500,
+                stake_pool: Pubkey::new_unique(),
+                unstake_delay: 10,
+            },
+        ];
+        
+        for protocol in protocols {
+            let discriminant = protocol.discriminant();
+            assert_eq!(ProtocolType::from_discriminant(discriminant), Some(protocol.kind()));
+            
+            // Matches the serialized variant tag clients see on-chain
+            assert_eq!(protocol.try_to_vec().unwrap()[0], discriminant);
+        }
+        
+        assert_eq!(ProtocolType::from_discriminant(3), None);
+    }
+    
+    #[test]
+    fn test_strategy_account_fits_largest_protocol() {
+        let strategy = Strategy {
+            protocol_type: ProtocolType::YieldFarming {
+                pair_id: Pubkey::new_unique(),
+                reward_multiplier: 10,
+                token_a_mint: Pubkey::new_unique(),
+                token_b_mint: Pubkey::new_unique(),
+                fee_tier: 1000,
+            },
+            current_balance: u64::MAX,
+            yield_rate: 50000,
+            volatility_score: 10000,
+            performance_score: 10000,
+            smoothed_score: 10000,
+            percentile_rank: 100,
+            last_updated: i64::MAX,
+            total_deposits: u64::MAX,
+            total_withdrawals: u64::MAX,
+            creation_time: i64::MAX,
+            ..test_fixtures::strategy()
+        };
+        
+        let serialized = strategy.try_to_vec().unwrap();
+        assert!(8 + serialized.len() <= Strategy::MAX_SIZE);
+    }
+    
+    #[test]
+    fn test_deposits_rejected_past_aum_cap() {
+        const SOL: u64 = 1_000_000_000;
+        let mut portfolio = Portfolio {
+            total_strategies: 0,
+            config: PortfolioConfig {
+                target_aum_cap: 10 * SOL,
+                ..PortfolioConfig::default()
+            },
+            ..test_fixtures::portfolio()
+        };
+        
+        // Deposit exactly up to the cap
+        for amount in [4 * SOL, 6 * SOL] {
+            let (new_total, over_cap) = portfolio.check_aum_deposit(amount).unwrap();
+            assert!(!over_cap);
+            portfolio.total_aum = new_total;
+        }
+        assert_eq!(portfolio.total_aum, 10 * SOL);
+        
+        // One more lamport is rejected under a hard cap
+        let err = portfolio.check_aum_deposit(1).unwrap_err();
+        assert_eq!(err, RebalancerError::AumCapExceeded.into());
+        
+        // A soft cap accepts it but flags the breach
+        portfolio.config.aum_cap_soft = true;
+        assert_eq!(portfolio.check_aum_deposit(1).unwrap(), (10 * SOL + 1, true));
+    }
+    
+    #[test]
+    fn test_partial_withdrawal_rescores_strategy() {
+        let config = PortfolioConfig::default();
+        let mut strategy = Strategy {
+            yield_rate: 8000,
+            volatility_score: 2000,
+            total_deposits: 0,
+            yield_rate_signed: 8000,
+            ..test_fixtures::strategy()
+        };
+        apply_balance_change(&mut strategy, 20_000_000_000, &config).unwrap();
+        strategy.set_rank_stale(false);
+        let score_before = strategy.performance_score;
+        
+        // Withdraw half: balance component shrinks, rank goes stale
+        let half = strategy.current_balance / 2;
+        apply_balance_change(&mut strategy, half, &config).unwrap();
+        assert_eq!(strategy.current_balance, 10_000_000_000);
+        assert!(strategy.performance_score < score_before);
+        assert_eq!(strategy.smoothed_score, strategy.performance_score);
+        assert!(strategy.rank_stale());
+    }
+    
+    #[test]
+    fn test_strategy_registered_event_decodes() {
+        let portfolio = Pubkey::new_unique();
+        let strategy_id = Pubkey::new_unique();
+        let protocol_type = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 30,
+        };
+        let event = StrategyRegisteredEvent::new(portfolio, strategy_id, &protocol_type, 5_000_000_000, 1_700_000_000);
+        
+        // Log payload: 8-byte event discriminator followed by the Borsh-encoded fields
+        let data = anchor_lang::Event::data(&event);
+        assert_eq!(&data[..8], StrategyRegisteredEvent::DISCRIMINATOR);
+        let decoded = StrategyRegisteredEvent::try_from_slice(&data[8..]).unwrap();
+        
+        assert_eq!(decoded.strategy_id, strategy_id);
+        assert_eq!(decoded.protocol_kind, 1);
+        assert_eq!(ProtocolType::from_discriminant(decoded.protocol_kind), Some(ProtocolKind::YieldFarming));
+        assert_eq!(decoded.initial_balance, 5_000_000_000);
+        assert_eq!(decoded.portfolio, portfolio);
+        assert_eq!(decoded.timestamp, 1_700_000_000);
+    }
+    
+    #[test]
+    fn test_verify_vault_funding() {
+        assert!(verify_vault_funding(0, 1_000_000_000, 1_000_000_000).is_ok());
+        assert!(verify_vault_funding(890_880, 1_000_890_880, 1_000_000_000).is_ok());
+        
+        // Short transfer and balance decrease are both mismatches
+        assert!(verify_vault_funding(0, 999_999_999, 1_000_000_000).is_err());
+        assert!(verify_vault_funding(1_000, 0, 1_000_000_000).is_err());
+    }
+    
+    #[test]
+    fn test_vault_remainder_stays_rent_exempt() {
+        let rent = Rent::default();
+        let floor = rent.minimum_balance(0);
+        let vault = 1_000_000_000 + floor;
+        
+        // Leaving exactly the floor, more than it, or nothing at all is fine
+        assert!(verify_vault_remainder(vault, 1_000_000_000, &rent).is_ok());
+        assert!(verify_vault_remainder(vault, 500_000_000, &rent).is_ok());
+        assert!(verify_vault_remainder(vault, vault, &rent).is_ok());
+        
+        // A dust remainder would strand the vault below rent exemption
+        let err = verify_vault_remainder(vault, vault - floor + 1, &rent).unwrap_err();
+        assert_eq!(err, RebalancerError::VaultBelowRentExempt.into());
+        let err = verify_vault_remainder(vault, vault - 1, &rent).unwrap_err();
+        assert_eq!(err, RebalancerError::VaultBelowRentExempt.into());
+        
+        // Asking for more than the vault holds
+        let err = verify_vault_remainder(vault, vault + 1, &rent).unwrap_err();
+        assert_eq!(err, RebalancerError::InsufficientBalance.into());
+    }
+}
@@ -0,0 +1,231 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use super::summary_pack::unpack_strategy_summary;
+
+#[derive(Accounts)]
+#[instruction(page: u32)]
+pub struct InitRegistryPage<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = StrategyRegistry::MAX_SIZE,
+        seeds = [b"registry", portfolio.key().as_ref(), &page.to_le_bytes()],
+        bump
+    )]
+    pub registry: Account<'info, StrategyRegistry>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct DeregisterStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    /// Registry page currently listing the strategy
+    #[account(
+        mut,
+        seeds = [b"registry", portfolio.key().as_ref(), &registry.page.to_le_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, StrategyRegistry>,
+
+    /// Summary pack PDA, supplied whether or not the pack exists so a packed
+    /// slot cannot outlive its strategy
+    /// CHECK: address pinned by the seeds; only read once the pack has been created
+    #[account(
+        mut,
+        seeds = [b"summary_pack", portfolio.key().as_ref()],
+        bump
+    )]
+    pub summary_pack: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+pub fn init_registry_page(ctx: Context<InitRegistryPage>, page: u32) -> Result<()> {
+    let registry = &mut ctx.accounts.registry;
+
+    registry.portfolio = ctx.accounts.portfolio.key();
+    registry.page = page;
+    registry.count = 0;
+    registry.strategy_ids = [Pubkey::default(); 32];
+    registry.bump = ctx.bumps.registry;
+
+    msg!("Registry page {} created for portfolio {}", page, registry.portfolio);
+
+    Ok(())
+}
+
+// Closes an emptied, deprecated strategy and drops it from the registry and summary pack
+pub fn deregister_strategy(ctx: Context<DeregisterStrategy>, strategy_id: Pubkey) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let strategy = &ctx.accounts.strategy;
+
+    require!(strategy.status == StrategyStatus::Deprecated, RebalancerError::InvalidStatusTransition);
+    require!(strategy.current_balance == 0, RebalancerError::InsufficientBalance);
+
+    ctx.accounts.registry.remove(&strategy_id)?;
+    if unpack_strategy_summary(&ctx.accounts.summary_pack, &strategy_id)? {
+        msg!("Strategy {} removed from summary pack", strategy_id);
+    }
+
+    portfolio.total_strategies = portfolio.total_strategies
+        .checked_sub(1)
+        .ok_or(RebalancerError::MathOverflow)?;
+
+    msg!("Strategy deregistered: {} ({} remaining)", strategy_id, portfolio.total_strategies);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_page() -> StrategyRegistry {
+        StrategyRegistry {
+            portfolio: Pubkey::new_unique(),
+            page: 0,
+            count: 0,
+            strategy_ids: [Pubkey::default(); 32],
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_registry_lists_registered_strategies() {
+        let mut registry = empty_page();
+        let ids: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+
+        for id in &ids {
+            registry.append(*id).unwrap();
+        }
+
+        assert_eq!(registry.strategy_ids(), ids.as_slice());
+
+        // Same id cannot be listed twice
+        assert!(registry.append(ids[0]).is_err());
+
+        // Deregistering keeps the remaining ids listed
+        registry.remove(&ids[0]).unwrap();
+        assert_eq!(registry.strategy_ids(), &[ids[2], ids[1]]);
+    }
+
+    #[test]
+    fn test_registry_page_capacity() {
+        let mut registry = empty_page();
+        for _ in 0..crate::constants::REGISTRY_PAGE_CAPACITY {
+            registry.append(Pubkey::new_unique()).unwrap();
+        }
+
+        assert!(registry.is_full());
+        assert!(registry.append(Pubkey::new_unique()).is_err());
+
+        let serialized = registry.try_to_vec().unwrap();
+        assert!(8 + serialized.len() <= StrategyRegistry::MAX_SIZE);
+    }
+
+    #[test]
+    fn test_deregister_requires_registry_page() {
+        use std::collections::BTreeSet;
+
+        let program_id = crate::ID;
+        let portfolio = crate::test_fixtures::portfolio();
+        let (portfolio_key, portfolio_bump) =
+            Pubkey::find_program_address(&[b"portfolio", portfolio.seed_manager.as_ref()], &program_id);
+        let portfolio = Portfolio { bump: portfolio_bump, ..portfolio };
+        let manager = portfolio.manager;
+
+        let strategy_id = Pubkey::new_unique();
+        let (strategy_key, strategy_bump) =
+            Pubkey::find_program_address(&[b"strategy", portfolio_key.as_ref(), strategy_id.as_ref()], &program_id);
+        let strategy = Strategy {
+            strategy_id,
+            status: StrategyStatus::Deprecated,
+            bump: strategy_bump,
+            ..crate::test_fixtures::strategy()
+        };
+        let (registry_key, registry_bump) =
+            Pubkey::find_program_address(&[b"registry", portfolio_key.as_ref(), &0u32.to_le_bytes()], &program_id);
+        let mut registry = StrategyRegistry { portfolio: portfolio_key, bump: registry_bump, ..empty_page() };
+        registry.append(strategy_id).unwrap();
+
+        let (mut portfolio_data, mut strategy_data, mut registry_data) = (Vec::new(), Vec::new(), Vec::new());
+        portfolio.try_serialize(&mut portfolio_data).unwrap();
+        strategy.try_serialize(&mut strategy_data).unwrap();
+        registry.try_serialize(&mut registry_data).unwrap();
+
+        let (pack_key, _) = Pubkey::find_program_address(&[b"summary_pack", portfolio_key.as_ref()], &program_id);
+        let mut lamports = [1_000_000_000u64; 5];
+        let [portfolio_lamports, strategy_lamports, registry_lamports, absent_lamports, manager_lamports] = &mut lamports;
+        let mut pack_lamports = 0u64;
+        let (mut absent_data, mut no_data, mut pack_data) = (Vec::<u8>::new(), Vec::<u8>::new(), Vec::<u8>::new());
+        let portfolio_info = AccountInfo::new(&portfolio_key, false, true, portfolio_lamports, &mut portfolio_data, &program_id, false, 0);
+        let strategy_info = AccountInfo::new(&strategy_key, false, true, strategy_lamports, &mut strategy_data, &program_id, false, 0);
+        let registry_info = AccountInfo::new(&registry_key, false, true, registry_lamports, &mut registry_data, &program_id, false, 0);
+        let absent_info = AccountInfo::new(&program_id, false, false, absent_lamports, &mut absent_data, &program_id, true, 0);
+        let system_program = Pubkey::default();
+        let pack_info = AccountInfo::new(&pack_key, false, true, &mut pack_lamports, &mut pack_data, &system_program, false, 0);
+        let manager_info = AccountInfo::new(&manager, true, true, manager_lamports, &mut no_data, &system_program, false, 0);
+
+        fn parse<'info>(infos: &'info [AccountInfo<'info>], strategy_id: &Pubkey) -> Result<DeregisterStrategy<'info>> {
+            let mut remaining = infos;
+            DeregisterStrategy::try_accounts(
+                &crate::ID,
+                &mut remaining,
+                &strategy_id.to_bytes(),
+                &mut DeregisterStrategyBumps::default(),
+                &mut BTreeSet::new(),
+            )
+        }
+
+        // Leaving the registry out (the optional-account placeholder) no longer parses,
+        // so a deregistration cannot leave a stale entry behind
+        let placeholder = [portfolio_info.clone(), strategy_info.clone(), absent_info, pack_info.clone(), manager_info.clone()];
+        assert!(parse(&placeholder, &strategy_id).is_err());
+        let omitted = [portfolio_info.clone(), strategy_info.clone(), pack_info.clone(), manager_info.clone()];
+        assert!(parse(&omitted, &strategy_id).is_err());
+
+        // The summary pack address is required even when the pack was never created
+        let no_pack = [portfolio_info.clone(), strategy_info.clone(), registry_info.clone(), manager_info.clone()];
+        assert!(parse(&no_pack, &strategy_id).is_err());
+
+        let full = [portfolio_info, strategy_info, registry_info, pack_info, manager_info];
+        let mut accounts = parse(&full, &strategy_id).unwrap();
+        deregister_strategy(
+            Context::new(&program_id, &mut accounts, &[], DeregisterStrategyBumps::default()),
+            strategy_id,
+        )
+        .unwrap();
+        assert!(accounts.registry.strategy_ids().is_empty());
+        assert_eq!(accounts.portfolio.total_strategies, 2);
+    }
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use super::strategy_accounts::*;
+
+#[derive(Accounts)]
+pub struct RepairStrategyCount<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    // remaining_accounts: every Strategy PDA of this portfolio (read-only), one per counted strategy
+}
+
+pub fn repair_strategy_count<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RepairStrategyCount<'info>>,
+    verified_count: u32,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+
+    // PROOF: EACH PASSED ACCOUNT MUST BE A STRATEGY PDA OF THIS PORTFOLIO
+    let mut proven_ids = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+        proven_ids.push(strategy.strategy_id);
+    }
+
+    let portfolio = &mut ctx.accounts.portfolio;
+    let previous_count = apply_verified_count(portfolio, verified_count, &proven_ids)?;
+
+    msg!("Strategy count repaired: {} -> {}", previous_count, verified_count);
+
+    emit!(StrategyCountRepairedEvent {
+        portfolio: portfolio_key,
+        previous_count,
+        verified_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Sets total_strategies to the verified count once the proof matches it exactly
+// (one distinct strategy per counted slot). Returns the previous count.
+pub fn apply_verified_count(
+    portfolio: &mut Portfolio,
+    verified_count: u32,
+    proven_ids: &[Pubkey],
+) -> Result<u32> {
+    require!(proven_ids.len() == verified_count as usize, RebalancerError::StrategyCountMismatch);
+
+    let mut unique_ids = proven_ids.to_vec();
+    unique_ids.sort();
+    unique_ids.dedup();
+    require!(unique_ids.len() == proven_ids.len(), RebalancerError::DuplicateStrategy);
+
+    let previous_count = portfolio.total_strategies;
+    portfolio.total_strategies = verified_count;
+
+    Ok(previous_count)
+}
+
+#[event]
+pub struct StrategyCountRepairedEvent {
+    pub portfolio: Pubkey,
+    pub previous_count: u32,
+    pub verified_count: u32,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn portfolio_with_count(total_strategies: u32) -> Portfolio {
+        Portfolio {
+            total_strategies,
+            ..test_fixtures::portfolio()
+        }
+    }
+
+    #[test]
+    fn test_repair_corrupted_count() {
+        // Counter drifted to 7 while only 3 strategies exist
+        let mut portfolio = portfolio_with_count(7);
+        let proven_ids: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+
+        let previous = apply_verified_count(&mut portfolio, 3, &proven_ids).unwrap();
+
+        assert_eq!(previous, 7);
+        assert_eq!(portfolio.total_strategies, 3);
+    }
+
+    #[test]
+    fn test_repair_rejects_mismatched_proof() {
+        let mut portfolio = portfolio_with_count(7);
+        let id = Pubkey::new_unique();
+
+        // Fewer accounts than claimed
+        assert!(apply_verified_count(&mut portfolio, 3, &[id, Pubkey::new_unique()]).is_err());
+        // Same strategy passed twice to pad the proof
+        assert!(apply_verified_count(&mut portfolio, 2, &[id, id]).is_err());
+        assert_eq!(portfolio.total_strategies, 7);
+    }
+}
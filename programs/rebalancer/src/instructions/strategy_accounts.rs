@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+// Upper bound on strategy accounts passed via remaining_accounts per call
+pub const MAX_STRATEGY_BATCH: usize = 10;
+
+// REMAINING-ACCOUNTS STRATEGY LOADER
+// Deserializes a Strategy (owner + discriminator checked by Anchor) and verifies
+// it is the PDA registered under this portfolio
+pub fn load_portfolio_strategy<'info>(
+    account_info: &'info AccountInfo<'info>,
+    portfolio_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Account<'info, Strategy>> {
+    let strategy: Account<'info, Strategy> = Account::try_from(account_info)?;
+
+    let expected_key = Pubkey::create_program_address(
+        &[
+            b"strategy",
+            portfolio_key.as_ref(),
+            strategy.strategy_id.as_ref(),
+            &[strategy.bump],
+        ],
+        program_id,
+    ).map_err(|_| RebalancerError::StrategyNotFound)?;
+    require_keys_eq!(expected_key, account_info.key(), RebalancerError::StrategyNotFound);
+
+    Ok(strategy)
+}
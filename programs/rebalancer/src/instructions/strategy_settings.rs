@@ -0,0 +1,427 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use crate::instructions::update_performance::{apply_balance_change, score_strategy};
+use crate::instructions::register_strategy::emit_aum_cap_warning;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct ConfigureStrategy<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct ManageRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+pub fn set_target_weight(
+    ctx: Context<ConfigureStrategy>,
+    _strategy_id: Pubkey,
+    target_weight_bps: u16,
+) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(target_weight_bps <= 10000, RebalancerError::InvalidAllocationPercentage);
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+
+    strategy.target_weight_bps = target_weight_bps;
+
+    msg!("Target weight set: strategy={}, weight={}bps", strategy.strategy_id, target_weight_bps);
+
+    Ok(())
+}
+
+pub fn set_strategy_pinned(
+    ctx: Context<ConfigureStrategy>,
+    _strategy_id: Pubkey,
+    pinned: bool,
+) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+
+    strategy.pinned = pinned;
+
+    msg!("Strategy pin updated: strategy={}, pinned={}", strategy.strategy_id, pinned);
+
+    Ok(())
+}
+
+pub fn set_quote_currency(
+    ctx: Context<ConfigureStrategy>,
+    _strategy_id: Pubkey,
+    quote_mint: Pubkey,
+    quote_decimals: u8,
+) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+    require!(quote_decimals <= 18, RebalancerError::InvalidAllocationPercentage);
+
+    strategy.quote_mint = quote_mint;
+    strategy.quote_decimals = quote_decimals;
+
+    msg!("Quote currency set: strategy={}, mint={}, decimals={}",
+         strategy.strategy_id, quote_mint, quote_decimals);
+
+    Ok(())
+}
+
+// Enabling reinvestment also sweeps rewards held so far into the balance
+pub fn set_reinvest_rewards(
+    ctx: Context<ManageRewards>,
+    _strategy_id: Pubkey,
+    reinvest_rewards: bool,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let portfolio = &mut ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+
+    let swept = set_reward_policy(portfolio, strategy, reinvest_rewards)?;
+
+    msg!("Reward policy updated: strategy={}, reinvest={}, swept={}",
+         strategy.strategy_id, reinvest_rewards, swept);
+
+    if swept > 0 && portfolio.config.target_aum_cap > 0 && portfolio.total_aum > portfolio.config.target_aum_cap {
+        emit_aum_cap_warning(portfolio, current_time);
+    }
+
+    Ok(())
+}
+
+pub fn accrue_rewards(
+    ctx: Context<ManageRewards>,
+    _strategy_id: Pubkey,
+    reward_amount: u64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    let portfolio = &mut ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+
+    let reinvested = strategy.accrue_rewards(reward_amount)?;
+    let over_cap = compound_rewards(portfolio, strategy, reinvested)?;
+
+    msg!("Rewards accrued: strategy={}, amount={}, balance={}, pending={}",
+         strategy.strategy_id, reward_amount, strategy.current_balance, strategy.pending_rewards);
+
+    if over_cap {
+        emit_aum_cap_warning(portfolio, current_time);
+    }
+
+    Ok(())
+}
+
+// Switches the reward policy; turning reinvestment on compounds pending_rewards.
+// Returns the amount swept.
+pub fn set_reward_policy(portfolio: &mut Portfolio, strategy: &mut Strategy, reinvest_rewards: bool) -> Result<u64> {
+    strategy.reinvest_rewards = reinvest_rewards;
+    if !reinvest_rewards || strategy.pending_rewards == 0 {
+        return Ok(0);
+    }
+
+    let swept = strategy.pending_rewards;
+    compound_rewards(portfolio, strategy, swept)?;
+    strategy.pending_rewards = 0;
+    Ok(swept)
+}
+
+// Credits reinvested rewards like a deposit: AUM cap, total_aum and rescoring.
+// Returns whether a soft AUM cap was exceeded.
+pub fn compound_rewards(portfolio: &mut Portfolio, strategy: &mut Strategy, amount: u64) -> Result<bool> {
+    if amount == 0 {
+        return Ok(false);
+    }
+
+    let (new_total_aum, over_cap) = portfolio.check_aum_deposit(amount)?;
+    let new_balance = strategy.current_balance
+        .checked_add(amount)
+        .ok_or(RebalancerError::BalanceOverflow)?;
+    apply_balance_change(strategy, new_balance, &portfolio.config)?;
+    portfolio.total_aum = new_total_aum;
+    Ok(over_cap)
+}
+
+pub fn update_protocol_params(
+    ctx: Context<ConfigureStrategy>,
+    _strategy_id: Pubkey,
+    protocol_type: ProtocolType,
+) -> Result<()> {
+    let config = ctx.accounts.portfolio.config;
+    let strategy = &mut ctx.accounts.strategy;
+
+    require!(strategy.status != StrategyStatus::Deprecated, RebalancerError::StrategyNotFound);
+
+    // Parameters may change, the protocol kind may not
+    require!(
+        strategy.protocol_type.kind() == protocol_type.kind(),
+        RebalancerError::InvalidProtocolType
+    );
+    protocol_type.validate()?;
+    config.check_reward_multiplier(&protocol_type)?;
+
+    strategy.protocol_type = protocol_type;
+
+    msg!("Protocol params updated: strategy={}, protocol={}",
+         strategy.strategy_id, protocol_type.get_protocol_name());
+
+    Ok(())
+}
+
+// Moves a paused strategy onto a different protocol (e.g. after a pool migration).
+// Balances and deposit/withdrawal accounting carry over unchanged; only the score
+// is re-derived, since protocol-dependent scoring may differ for the new variant
+pub fn migrate_protocol_type(
+    ctx: Context<ConfigureStrategy>,
+    _strategy_id: Pubkey,
+    new_protocol_type: ProtocolType,
+) -> Result<()> {
+    let config = ctx.accounts.portfolio.config;
+    let strategy = &mut ctx.accounts.strategy;
+
+    let previous = apply_protocol_migration(strategy, new_protocol_type, &config)?;
+
+    msg!("Protocol migrated: strategy={}, {} -> {}, balance={}",
+         strategy.strategy_id, previous.get_protocol_name(),
+         new_protocol_type.get_protocol_name(), strategy.current_balance);
+
+    emit!(ProtocolMigratedEvent {
+        portfolio: ctx.accounts.portfolio.key(),
+        strategy_id: strategy.strategy_id,
+        previous_protocol_kind: previous.discriminant(),
+        new_protocol_kind: new_protocol_type.discriminant(),
+        current_balance: strategy.current_balance,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Returns the replaced protocol type
+pub fn apply_protocol_migration(
+    strategy: &mut Strategy,
+    new_protocol_type: ProtocolType,
+    config: &PortfolioConfig,
+) -> Result<ProtocolType> {
+    require!(strategy.status == StrategyStatus::Paused, RebalancerError::InvalidStatusTransition);
+    new_protocol_type.validate()?;
+    config.check_reward_multiplier(&new_protocol_type)?;
+
+    let previous = std::mem::replace(&mut strategy.protocol_type, new_protocol_type);
+    strategy.performance_score = score_strategy(strategy, config)?;
+    if !config.smooth_scores {
+        strategy.smoothed_score = strategy.performance_score;
+    }
+    strategy.set_rank_stale(true);
+
+    Ok(previous)
+}
+
+#[event]
+pub struct ProtocolMigratedEvent {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub previous_protocol_kind: u8,   // ProtocolType::discriminant
+    pub new_protocol_kind: u8,
+    pub current_balance: u64,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn farm(reward_multiplier: u8) -> ProtocolType {
+        ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 300,
+        }
+    }
+
+    fn farm_strategy(reinvest_rewards: bool) -> Strategy {
+        Strategy {
+            protocol_type: farm(2),
+            current_balance: 1_000_000_000,
+            yield_rate: 1200,
+            volatility_score: 6000,
+            performance_score: 5000,
+            smoothed_score: 5000,
+            reinvest_rewards,
+            ..test_fixtures::strategy()
+        }
+    }
+
+    fn accrue(portfolio: &mut Portfolio, strategy: &mut Strategy, reward_amount: u64) -> Result<bool> {
+        let reinvested = strategy.accrue_rewards(reward_amount)?;
+        compound_rewards(portfolio, strategy, reinvested)
+    }
+
+    #[test]
+    fn test_reinvested_rewards_compound_into_balance() {
+        let mut portfolio = Portfolio { total_aum: 1_000_000_000, ..test_fixtures::portfolio() };
+        let mut strategy = farm_strategy(true);
+        let score_before = strategy.performance_score;
+        accrue(&mut portfolio, &mut strategy, 50_000_000).unwrap();
+
+        assert_eq!(strategy.current_balance, 1_050_000_000);
+        assert_eq!(strategy.pending_rewards, 0);
+
+        // Credited like a deposit: counted in AUM, rescored, flagged for re-ranking
+        assert_eq!(portfolio.total_aum, 1_050_000_000);
+        assert_ne!(strategy.performance_score, score_before);
+        assert!(strategy.rank_stale());
+
+        // A hard AUM cap applies to compounding too
+        portfolio.config.target_aum_cap = 1_060_000_000;
+        assert_eq!(
+            accrue(&mut portfolio, &mut strategy, 20_000_000).unwrap_err(),
+            RebalancerError::AumCapExceeded.into()
+        );
+        assert_eq!(strategy.current_balance, 1_050_000_000);
+        portfolio.config.aum_cap_soft = true;
+        assert!(accrue(&mut portfolio, &mut strategy, 20_000_000).unwrap());
+        assert_eq!(portfolio.total_aum, 1_070_000_000);
+    }
+
+    #[test]
+    fn test_held_rewards_land_in_pending() {
+        let mut portfolio = Portfolio { total_aum: 1_000_000_000, ..test_fixtures::portfolio() };
+        let mut strategy = farm_strategy(false);
+        accrue(&mut portfolio, &mut strategy, 50_000_000).unwrap();
+        accrue(&mut portfolio, &mut strategy, 25_000_000).unwrap();
+
+        assert_eq!(strategy.current_balance, 1_000_000_000);
+        assert_eq!(strategy.pending_rewards, 75_000_000);
+        assert_eq!(portfolio.total_aum, 1_000_000_000);
+
+        // Turning reinvestment on sweeps the held rewards into the balance
+        assert_eq!(set_reward_policy(&mut portfolio, &mut strategy, true).unwrap(), 75_000_000);
+        assert_eq!((strategy.current_balance, strategy.pending_rewards), (1_075_000_000, 0));
+        assert_eq!(portfolio.total_aum, 1_075_000_000);
+        assert_eq!(set_reward_policy(&mut portfolio, &mut strategy, false).unwrap(), 0);
+
+        // Lending strategies do not accrue protocol rewards
+        strategy.protocol_type = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        assert!(strategy.accrue_rewards(1).is_err());
+    }
+
+    #[test]
+    fn test_lending_strategy_migrates_to_liquid_staking() {
+        let mut strategy = farm_strategy(false);
+        strategy.protocol_type = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        strategy.total_withdrawals = 200_000_000;
+        strategy.high_water_mark = 1_100_000_000;
+        let staking = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        let config = PortfolioConfig::default();
+
+        // Only paused strategies may migrate
+        assert!(apply_protocol_migration(&mut strategy, staking, &config).is_err());
+        assert_eq!(strategy.protocol_type.kind(), ProtocolKind::StableLending);
+
+        strategy.status = StrategyStatus::Paused;
+        let previous = apply_protocol_migration(&mut strategy, staking, &config).unwrap();
+        assert_eq!(previous.kind(), ProtocolKind::StableLending);
+        assert_eq!(strategy.protocol_type.kind(), ProtocolKind::LiquidStaking);
+
+        // Balances and accounting persist across the migration
+        assert_eq!(strategy.current_balance, 1_000_000_000);
+        assert_eq!(strategy.total_deposits, 1_000_000_000);
+        assert_eq!(strategy.total_withdrawals, 200_000_000);
+        assert_eq!(strategy.high_water_mark, 1_100_000_000);
+        assert_eq!(strategy.status, StrategyStatus::Paused);
+        assert!(strategy.rank_stale());
+
+        // The new variant must itself be valid
+        let invalid = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::default(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        assert!(apply_protocol_migration(&mut strategy, invalid, &config).is_err());
+    }
+
+    #[test]
+    fn test_portfolio_reward_multiplier_cap() {
+        let conservative = PortfolioConfig {
+            max_reward_multiplier: 3,
+            ..PortfolioConfig::default()
+        };
+        assert!(conservative.validate().is_ok());
+
+        // 5x farm passes the global check but not this portfolio's cap
+        let farm_5x = farm(5);
+        assert!(farm_5x.validate().is_ok());
+        assert!(conservative.check_reward_multiplier(&farm_5x).is_err());
+        assert!(conservative.check_reward_multiplier(&farm(3)).is_ok());
+
+        // Cap only applies to YieldFarming
+        let lending = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        assert!(conservative.check_reward_multiplier(&lending).is_ok());
+
+        let invalid_cap = PortfolioConfig {
+            max_reward_multiplier: 0,
+            ..PortfolioConfig::default()
+        };
+        assert!(invalid_cap.validate().is_err());
+    }
+}
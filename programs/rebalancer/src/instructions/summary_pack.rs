@@ -0,0 +1,300 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use super::execute_ranking::{calculate_percentile_rankings_with_config, canonicalize_strategy_order, StrategyData};
+use super::strategy_accounts::{load_portfolio_strategy, MAX_STRATEGY_BATCH};
+
+#[derive(Accounts)]
+pub struct InitSummaryPack<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = StrategySummaryPack::MAX_SIZE,
+        seeds = [b"summary_pack", portfolio.key().as_ref()],
+        bump
+    )]
+    pub summary_pack: Box<Account<'info, StrategySummaryPack>>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSummaryPack<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        mut,
+        seeds = [b"summary_pack", portfolio.key().as_ref()],
+        bump = summary_pack.bump
+    )]
+    pub summary_pack: Box<Account<'info, StrategySummaryPack>>,
+
+    pub manager: Signer<'info>,
+
+    // remaining_accounts: up to MAX_STRATEGY_BATCH Strategy PDAs of this portfolio
+}
+
+#[derive(Accounts)]
+pub struct RankSummaryPack<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        seeds = [b"summary_pack", portfolio.key().as_ref()],
+        bump = summary_pack.bump
+    )]
+    pub summary_pack: Box<Account<'info, StrategySummaryPack>>,
+
+    pub manager: Signer<'info>,
+}
+
+pub fn init_summary_pack(ctx: Context<InitSummaryPack>) -> Result<()> {
+    let pack = &mut ctx.accounts.summary_pack;
+
+    pack.portfolio = ctx.accounts.portfolio.key();
+    pack.count = 0;
+    pack.summaries = [PackedStrategySummary::default(); 128];
+    pack.bump = ctx.bumps.summary_pack;
+
+    msg!("Strategy summary pack created for portfolio {}", pack.portfolio);
+
+    Ok(())
+}
+
+// Copies the passed strategies into the pack: active ones are refreshed or added,
+// paused and deprecated ones are dropped so the pack only ranks what batch ranking would
+pub fn sync_summary_pack<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateSummaryPack<'info>>,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let config = ctx.accounts.portfolio.config;
+
+    require!(!ctx.remaining_accounts.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STRATEGY_BATCH, RebalancerError::TooManyStrategies);
+
+    let pack = &mut ctx.accounts.summary_pack;
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = load_portfolio_strategy(account_info, &portfolio_key, ctx.program_id)?;
+        if strategy.status == StrategyStatus::Active {
+            pack.upsert(packed_summary(&strategy, &config))?;
+        } else {
+            pack.remove(&strategy.strategy_id);
+        }
+    }
+
+    msg!("Summary pack synced: {} strategies packed", pack.count);
+
+    Ok(())
+}
+
+// Ranks every packed strategy in one pass and reports the underperformers; the
+// pack itself is left untouched, strategy ranks are only written by batch ranking
+pub fn rank_summary_pack(ctx: Context<RankSummaryPack>) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let pack = &ctx.accounts.summary_pack;
+
+    let underperformers = rank_packed_summaries(pack, portfolio.rebalance_threshold, &portfolio.config)?;
+
+    let event = PackedRankingEvent {
+        portfolio: portfolio.key(),
+        strategies_ranked: pack.count as u32,
+        underperformers: underperformers.len() as u32,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+
+    msg!("Packed ranking: portfolio={}, ranked={}, underperformers={}",
+         event.portfolio, event.strategies_ranked, event.underperformers);
+
+    emit!(event);
+
+    Ok(())
+}
+
+// Drops a strategy's slot straight from the pack account; a portfolio that never
+// created its pack has nothing to drop. Returns whether a slot was removed.
+pub fn unpack_strategy_summary(summary_pack: &AccountInfo, strategy_id: &Pubkey) -> Result<bool> {
+    if summary_pack.owner != &crate::ID {
+        return Ok(false);
+    }
+
+    let mut data = summary_pack.try_borrow_mut_data()?;
+    let mut pack = StrategySummaryPack::try_deserialize(&mut &data[..])?;
+    if !pack.remove(strategy_id) {
+        return Ok(false);
+    }
+    pack.try_serialize(&mut &mut data[..])?;
+    Ok(true)
+}
+
+pub fn packed_summary(strategy: &Strategy, config: &PortfolioConfig) -> PackedStrategySummary {
+    PackedStrategySummary {
+        strategy_id: strategy.strategy_id,
+        performance_score: strategy.ranking_score(config.smooth_scores),
+        current_balance: strategy.current_balance,
+        volatility_score: strategy.volatility_score,
+        percentile_rank: strategy.percentile_rank,
+    }
+}
+
+// Same ranking as batch ranking (calculate_percentile_rankings_with_config), fed from
+// the pack instead of Strategy accounts; returns the underperformers
+pub fn rank_packed_summaries(
+    pack: &StrategySummaryPack,
+    rebalance_threshold: u8,
+    config: &PortfolioConfig,
+) -> Result<Vec<Pubkey>> {
+    let mut strategies: Vec<StrategyData> = pack
+        .summaries()
+        .iter()
+        .map(|summary| StrategyData {
+            strategy_id: summary.strategy_id,
+            performance_score: summary.performance_score,
+            current_balance: summary.current_balance,
+            volatility_score: summary.volatility_score,
+            percentile_rank: summary.percentile_rank,
+            rebalance_threshold,
+        })
+        .collect();
+    canonicalize_strategy_order(&mut strategies);
+
+    calculate_percentile_rankings_with_config(&mut strategies, config)
+}
+
+#[event]
+pub struct PackedRankingEvent {
+    pub portfolio: Pubkey,
+    pub strategies_ranked: u32,
+    pub underperformers: u32,
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_pack() -> StrategySummaryPack {
+        StrategySummaryPack {
+            portfolio: Pubkey::new_unique(),
+            count: 0,
+            summaries: [PackedStrategySummary::default(); 128],
+            bump: 255,
+        }
+    }
+
+    fn summary(performance_score: u64) -> PackedStrategySummary {
+        PackedStrategySummary {
+            strategy_id: Pubkey::new_unique(),
+            performance_score,
+            current_balance: 1_000_000_000,
+            volatility_score: 3000,
+            percentile_rank: 0,
+        }
+    }
+
+    #[test]
+    fn test_rank_hundred_packed_strategies() {
+        let mut pack = empty_pack();
+        let summaries: Vec<PackedStrategySummary> = (1..=100u64).map(|i| summary(i * 50)).collect();
+        for s in summaries.iter() {
+            pack.upsert(*s).unwrap();
+        }
+        assert_eq!(pack.count, 100);
+
+        let config = PortfolioConfig::default();
+        let underperformers = rank_packed_summaries(&pack, 25, &config).unwrap();
+
+        // Ranking reads the pack without rewriting the synced slots
+        assert_eq!(pack.summaries(), summaries.as_slice());
+
+        // Same result as ranking the equivalent StrategyData directly
+        let mut direct: Vec<StrategyData> = summaries
+            .iter()
+            .map(|s| StrategyData {
+                strategy_id: s.strategy_id,
+                performance_score: s.performance_score,
+                current_balance: s.current_balance,
+                volatility_score: s.volatility_score,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            })
+            .collect();
+        assert_eq!(underperformers, calculate_percentile_rankings_with_config(&mut direct, &config).unwrap());
+        assert!(underperformers.contains(&summaries[0].strategy_id));
+        assert!(!underperformers.contains(&summaries[99].strategy_id));
+
+        // A full pack fits in its account
+        let serialized = pack.try_to_vec().unwrap();
+        assert_eq!(8 + serialized.len(), StrategySummaryPack::MAX_SIZE);
+    }
+
+    #[test]
+    fn test_pack_upsert_and_remove() {
+        let mut pack = empty_pack();
+        let first = summary(1000);
+        let second = summary(2000);
+        pack.upsert(first).unwrap();
+        pack.upsert(second).unwrap();
+
+        // Re-syncing a strategy refreshes its slot rather than adding another
+        pack.upsert(PackedStrategySummary { performance_score: 9000, ..first }).unwrap();
+        assert_eq!(pack.count, 2);
+        assert_eq!(pack.summaries()[0].performance_score, 9000);
+
+        assert!(pack.remove(&first.strategy_id));
+        assert!(!pack.remove(&first.strategy_id));
+        assert_eq!(pack.summaries(), &[second]);
+
+        while !pack.is_full() {
+            pack.upsert(summary(500)).unwrap();
+        }
+        assert!(pack.upsert(summary(500)).is_err());
+        assert!(pack.upsert(second).is_ok());
+    }
+
+    #[test]
+    fn test_unpack_strategy_summary_from_account() {
+        let program_id = crate::ID;
+        let key = Pubkey::new_unique();
+        let (kept, dropped) = (summary(1000), summary(2000));
+        let mut pack = empty_pack();
+        pack.upsert(kept).unwrap();
+        pack.upsert(dropped).unwrap();
+
+        let mut data = Vec::new();
+        pack.try_serialize(&mut data).unwrap();
+        data.resize(StrategySummaryPack::MAX_SIZE, 0);
+        let mut lamports = 1_000_000_000u64;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+
+        // The slot is removed from the account itself; a second pass finds nothing
+        assert!(unpack_strategy_summary(&info, &dropped.strategy_id).unwrap());
+        assert!(!unpack_strategy_summary(&info, &dropped.strategy_id).unwrap());
+        let stored = StrategySummaryPack::try_deserialize(&mut &info.data.borrow()[..]).unwrap();
+        assert_eq!(stored.summaries(), &[kept]);
+
+        // A pack that was never created is a system-owned empty account
+        let system_program = Pubkey::default();
+        let (mut no_lamports, mut no_data) = (0u64, Vec::<u8>::new());
+        let uncreated = AccountInfo::new(&key, false, true, &mut no_lamports, &mut no_data, &system_program, false, 0);
+        assert!(!unpack_strategy_summary(&uncreated, &kept.strategy_id).unwrap());
+    }
+}
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+#[derive(Accounts)]
+pub struct ManageTreasuryWhitelist<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = TreasuryWhitelist::MAX_SIZE,
+        seeds = [b"treasuries", portfolio.key().as_ref()],
+        bump
+    )]
+    pub treasury_whitelist: Account<'info, TreasuryWhitelist>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_authorized_treasury(
+    ctx: Context<ManageTreasuryWhitelist>,
+    treasury: Pubkey,
+) -> Result<()> {
+    let whitelist = &mut ctx.accounts.treasury_whitelist;
+    if whitelist.portfolio == Pubkey::default() {
+        whitelist.portfolio = ctx.accounts.portfolio.key();
+        whitelist.bump = ctx.bumps.treasury_whitelist;
+    }
+
+    whitelist.add(treasury)?;
+
+    msg!("Treasury authorized: {} ({} of {})",
+         treasury, whitelist.count, crate::constants::MAX_AUTHORIZED_TREASURIES);
+
+    Ok(())
+}
+
+pub fn remove_authorized_treasury(
+    ctx: Context<ManageTreasuryWhitelist>,
+    treasury: Pubkey,
+) -> Result<()> {
+    let whitelist = &mut ctx.accounts.treasury_whitelist;
+
+    whitelist.remove(&treasury)?;
+
+    msg!("Treasury deauthorized: {} ({} remaining)", treasury, whitelist.count);
+
+    Ok(())
+}
+
+// Fee allocations may only pay out to whitelisted treasuries; without a whitelist
+// no fee destination is authorized
+pub fn check_fee_destinations(
+    allocations: &[CapitalAllocation],
+    whitelist: Option<&TreasuryWhitelist>,
+) -> Result<()> {
+    for allocation in allocations {
+        if matches!(
+            allocation.allocation_type,
+            AllocationType::PlatformFee | AllocationType::ManagerIncentive
+        ) {
+            let authorized = whitelist.is_some_and(|w| w.is_authorized(&allocation.strategy_id));
+            require!(authorized, RebalancerError::InvalidTreasury);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocation(strategy_id: Pubkey, allocation_type: AllocationType) -> CapitalAllocation {
+        CapitalAllocation { strategy_id, amount: 1_000_000, allocation_type }
+    }
+
+    #[test]
+    fn test_fee_to_unlisted_treasury_rejected() {
+        let platform_treasury = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+
+        let mut whitelist = TreasuryWhitelist {
+            portfolio: Pubkey::new_unique(),
+            treasuries: [Pubkey::default(); 4],
+            count: 0,
+            bump: 255,
+        };
+        whitelist.add(platform_treasury).unwrap();
+
+        let honest = [
+            allocation(Pubkey::new_unique(), AllocationType::TopPerformer),
+            allocation(platform_treasury, AllocationType::PlatformFee),
+        ];
+        assert!(check_fee_destinations(&honest, Some(&whitelist)).is_ok());
+
+        let redirected = [
+            allocation(Pubkey::new_unique(), AllocationType::TopPerformer),
+            allocation(attacker, AllocationType::ManagerIncentive),
+        ];
+        assert!(check_fee_destinations(&redirected, Some(&whitelist)).is_err());
+
+        // No whitelist: strategy-only plans pass, any fee is rejected
+        assert!(check_fee_destinations(&honest[..1], None).is_ok());
+        assert!(check_fee_destinations(&honest, None).is_err());
+
+        // Removing a treasury revokes it
+        whitelist.remove(&platform_treasury).unwrap();
+        assert!(check_fee_destinations(&honest, Some(&whitelist)).is_err());
+    }
+
+    #[test]
+    fn test_whitelist_capacity_and_duplicates() {
+        let mut whitelist = TreasuryWhitelist {
+            portfolio: Pubkey::new_unique(),
+            treasuries: [Pubkey::default(); 4],
+            count: 0,
+            bump: 255,
+        };
+        let treasuries: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        for treasury in &treasuries {
+            whitelist.add(*treasury).unwrap();
+        }
+
+        assert!(whitelist.add(treasuries[0]).is_err());
+        assert!(whitelist.add(Pubkey::new_unique()).is_err());
+
+        // Swap-remove keeps the remaining entries authorized
+        whitelist.remove(&treasuries[1]).unwrap();
+        assert_eq!(whitelist.count, 3);
+        assert!(whitelist.is_authorized(&treasuries[3]));
+        assert!(!whitelist.is_authorized(&treasuries[1]));
+    }
+}
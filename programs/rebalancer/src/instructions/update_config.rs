@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+#[derive(Accounts)]
+pub struct UpdatePortfolioConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+pub fn update_portfolio_config(
+    ctx: Context<UpdatePortfolioConfig>,
+    config: PortfolioConfig,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+
+    // VALIDATE THE FULL CONFIG BEFORE ANY STATE CHANGE
+    config.validate()?;
+    config.validate_against_interval(portfolio.min_rebalance_interval)?;
+    check_fee_routing_update(portfolio, &config)?;
+
+    // Stored scores no longer match the formula; strategies need a performance update
+    if portfolio.config.scoring_changed(&config) {
+        portfolio.scoring_epoch = portfolio.scoring_epoch.wrapping_add(1);
+        msg!("Scoring parameters changed: scoring epoch now {}", portfolio.scoring_epoch);
+    }
+
+    portfolio.config = config;
+
+    msg!("Portfolio config updated: threshold floor={}%, ceiling={}%",
+         config.threshold_floor, config.threshold_ceiling);
+
+    emit!(PortfolioConfigUpdatedEvent::from_portfolio(
+        portfolio.key(),
+        portfolio,
+        Clock::get()?.unix_timestamp,
+    ));
+
+    Ok(())
+}
+
+// Fees of a chunked plan are classified against the live config on every call
+pub fn check_fee_routing_update(portfolio: &Portfolio, config: &PortfolioConfig) -> Result<()> {
+    require!(
+        portfolio.allocation_cursor() == 0 || !portfolio.config.fee_routing_changed(config),
+        RebalancerError::AllocationPlanInProgress
+    );
+    Ok(())
+}
+
+pub fn set_performance_fee(
+    ctx: Context<UpdatePortfolioConfig>,
+    performance_fee_bps: u16,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+
+    Portfolio::validate_performance_fee(performance_fee_bps)?;
+
+    let previous_fee_bps = portfolio.performance_fee_bps;
+    portfolio.performance_fee_bps = performance_fee_bps;
+
+    msg!("Performance fee updated: {}bps -> {}bps", previous_fee_bps, performance_fee_bps);
+
+    emit!(PortfolioConfigUpdatedEvent::from_portfolio(
+        portfolio.key(),
+        portfolio,
+        Clock::get()?.unix_timestamp,
+    ));
+
+    Ok(())
+}
+
+#[event]
+pub struct PortfolioConfigUpdatedEvent {
+    pub portfolio: Pubkey,
+    pub config: PortfolioConfig,
+    pub performance_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+impl PortfolioConfigUpdatedEvent {
+    pub fn from_portfolio(portfolio_key: Pubkey, portfolio: &Portfolio, timestamp: i64) -> Self {
+        PortfolioConfigUpdatedEvent {
+            portfolio: portfolio_key,
+            config: portfolio.config,
+            performance_fee_bps: portfolio.performance_fee_bps,
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_validation() {
+        assert!(PortfolioConfig::default().validate().is_ok());
+
+        // Floor above ceiling is rejected
+        let inverted = PortfolioConfig {
+            threshold_floor: 30,
+            threshold_ceiling: 20,
+            ..PortfolioConfig::default()
+        };
+        assert!(inverted.validate().is_err());
+
+        // Bounds outside 1-50 are rejected
+        let zero_floor = PortfolioConfig {
+            threshold_floor: 0,
+            ..PortfolioConfig::default()
+        };
+        assert!(zero_floor.validate().is_err());
+
+        let high_ceiling = PortfolioConfig {
+            threshold_ceiling: 51,
+            ..PortfolioConfig::default()
+        };
+        assert!(high_ceiling.validate().is_err());
+    }
+
+    #[test]
+    fn test_emergency_cooldown_below_rebalance_interval() {
+        let hourly = 3600i64;
+
+        // Default 5-minute emergency cooldown fits under any valid interval
+        assert!(PortfolioConfig::default().validate_against_interval(hourly).is_ok());
+
+        // An emergency path slower than (or as slow as) the normal one is rejected
+        let slow_emergency = PortfolioConfig {
+            emergency_cooldown: 7200,
+            ..PortfolioConfig::default()
+        };
+        assert!(slow_emergency.validate().is_ok());
+        let err = slow_emergency.validate_against_interval(hourly).unwrap_err();
+        assert_eq!(err, RebalancerError::InvalidRebalanceInterval.into());
+
+        let equal = PortfolioConfig {
+            emergency_cooldown: 3600,
+            ..PortfolioConfig::default()
+        };
+        assert!(equal.validate_against_interval(hourly).is_err());
+    }
+
+    #[test]
+    fn test_performance_fee_bound() {
+        assert!(Portfolio::validate_performance_fee(2000).is_ok());
+        assert!(Portfolio::validate_performance_fee(3000).is_ok());
+
+        let err = Portfolio::validate_performance_fee(5000).unwrap_err();
+        assert_eq!(err, RebalancerError::InvalidPerformanceFee.into());
+    }
+
+    #[test]
+    fn test_fee_routing_frozen_while_plan_in_progress() {
+        let mut portfolio = crate::test_fixtures::portfolio();
+        let reinvest = PortfolioConfig {
+            fee_disposition: FeeDisposition::Reinvest,
+            fee_treasury_strategy: Pubkey::new_unique(),
+            ..portfolio.config
+        };
+        let retuned = PortfolioConfig {
+            rank_hysteresis: portfolio.config.rank_hysteresis + 1,
+            ..portfolio.config
+        };
+
+        // Between plans any routing change is accepted
+        assert!(check_fee_routing_update(&portfolio, &reinvest).is_ok());
+
+        // Mid-plan only changes that leave fee routing alone go through
+        portfolio.set_allocation_cursor(10, 0xfeed);
+        let err = check_fee_routing_update(&portfolio, &reinvest).unwrap_err();
+        assert_eq!(err, RebalancerError::AllocationPlanInProgress.into());
+        assert!(check_fee_routing_update(&portfolio, &retuned).is_ok());
+
+        // Abandoning the plan lifts the freeze; there is nothing left to abandon after
+        assert_eq!(portfolio.abandon_allocation_plan().unwrap(), 10);
+        assert_eq!(portfolio.allocation_plan_tag(), 0);
+        assert!(check_fee_routing_update(&portfolio, &reinvest).is_ok());
+        let err = portfolio.abandon_allocation_plan().unwrap_err();
+        assert_eq!(err, RebalancerError::NoAllocationPlanInProgress.into());
+    }
+}
@@ -7,7 +7,7 @@ use crate::errors::*;
 pub struct UpdatePerformance<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
         bump = portfolio.bump,
         has_one = manager @ RebalancerError::InvalidManager
     )]
@@ -32,31 +32,128 @@ pub fn update_performance(
     volatility_score: u32,
     current_balance: u64,
 ) -> Result<()> {
-    let strategy = &mut ctx.accounts.strategy;
+    Strategy::validate_yield_rate(yield_rate)?;
+    process_performance_update(ctx, yield_rate as i64, volatility_score, current_balance)
+}
+
+// Signed variant: yield_rate_signed < 0 reports a losing strategy
+pub fn update_performance_signed(
+    ctx: Context<UpdatePerformance>,
+    _strategy_id: Pubkey,
+    yield_rate_signed: i64,
+    volatility_score: u32,
+    current_balance: u64,
+) -> Result<()> {
+    process_performance_update(ctx, yield_rate_signed, volatility_score, current_balance)
+}
+
+// Quote-denominated variant: balance is in the strategy's quote_mint units and is
+// normalized to lamports with the oracle price before scoring
+pub fn update_performance_quoted(
+    ctx: Context<UpdatePerformance>,
+    _strategy_id: Pubkey,
+    yield_rate: u64,
+    volatility_score: u32,
+    quote_balance: u64,
+    quote_price_lamports: u64,
+) -> Result<()> {
+    Strategy::validate_yield_rate(yield_rate)?;
+    let current_balance = ctx.accounts.strategy.normalize_balance(quote_balance, quote_price_lamports)?;
+    msg!("Quote balance {} normalized to {} lamports at price {}",
+         quote_balance, current_balance, quote_price_lamports);
+    process_performance_update(ctx, yield_rate as i64, volatility_score, current_balance)
+}
+
+fn process_performance_update(
+    ctx: Context<UpdatePerformance>,
+    yield_rate_signed: i64,
+    volatility_score: u32,
+    current_balance: u64,
+) -> Result<()> {
+    let config = ctx.accounts.portfolio.config;
     let current_time = Clock::get()?.unix_timestamp;
+    apply_performance_update(
+        &mut ctx.accounts.strategy,
+        &config,
+        yield_rate_signed,
+        volatility_score,
+        current_balance,
+        true, // Every entry point is rate limited
+        current_time,
+    )?;
     
+    // Re-scored under the current formula
+    ctx.accounts.strategy.scoring_epoch = ctx.accounts.portfolio.scoring_epoch;
+    Ok(())
+}
+
+// Core of every performance update, with the timestamp supplied by the caller
+pub fn apply_performance_update(
+    strategy: &mut Strategy,
+    config: &PortfolioConfig,
+    yield_rate_signed: i64,
+    volatility_score: u32,
+    current_balance: u64,
+    enforce_interval: bool,
+    current_time: i64,
+) -> Result<()> {
     // COMPREHENSIVE INPUT VALIDATIONS
-    Strategy::validate_yield_rate(yield_rate)?;
-    Strategy::validate_volatility_score(volatility_score)?;
+    Strategy::validate_signed_yield_rate(yield_rate_signed)?;
+    let volatility = VolatilityScore::from_bps(volatility_score)?;
     Strategy::validate_balance_update(current_balance)?;
-    require!(strategy.status == StrategyStatus::Active, RebalancerError::StrategyNotFound);
+    // Strategies auto-paused for stale metrics accept the refresh that un-stales them
+    require!(
+        strategy.status == StrategyStatus::Active || strategy.auto_paused(),
+        RebalancerError::StrategyNotFound
+    );
+    if enforce_interval {
+        check_update_interval(strategy.last_performance_update, current_time, config.min_update_interval)?;
+    }
     
-    // UPDATE STRATEGY METRICS
+    // UPDATE STRATEGY METRICS (unsigned field floors losses at 0 for legacy readers)
+    let yield_rate = yield_rate_signed.max(0) as u64;
     strategy.yield_rate = yield_rate;
-    strategy.volatility_score = volatility_score;
+    strategy.yield_rate_signed = yield_rate_signed;
+    strategy.volatility_score = volatility.bps();
     strategy.current_balance = current_balance;
     strategy.last_updated = current_time;
+    strategy.last_performance_update = current_time;
     
     // CALCULATE PERFORMANCE SCORE WITH WEIGHTED FORMULA
-    strategy.performance_score = calculate_performance_score(
-        yield_rate,
-        current_balance,
-        volatility_score,
-    )?;
+    strategy.performance_score = score_strategy(strategy, config)?;
     
-    msg!("Performance updated: strategy={}, yield={}bps, volatility={}, balance={}, score={}", 
-         strategy.strategy_id, yield_rate, volatility_score, current_balance, strategy.performance_score);
+    // SMOOTHED SCORE FOR RANKING (instantaneous score kept for display)
+    strategy.smoothed_score = if config.smooth_scores && strategy.smoothed_score > 0 {
+        calculate_smoothed_score(
+            strategy.smoothed_score,
+            strategy.performance_score,
+            crate::constants::SCORE_EMA_ALPHA_BPS,
+        )?
+    } else {
+        strategy.performance_score
+    };
     
+    if strategy.auto_paused() {
+        strategy.transition_status(StrategyStatus::Active)?;
+        msg!("Strategy {} reactivated: stale metrics refreshed", strategy.strategy_id);
+    }
+    
+    msg!("Performance updated: strategy={}, yield={}bps, volatility={}, balance={}, score={}, smoothed={}", 
+         strategy.strategy_id, yield_rate_signed, volatility_score, current_balance,
+         strategy.performance_score, strategy.smoothed_score);
+    
+    Ok(())
+}
+
+// UPDATE RATE LIMIT
+// Spaced from the previous performance update; rankings in between do not count
+pub fn check_update_interval(
+    last_performance_update: i64,
+    current_time: i64,
+    min_update_interval: u32,
+) -> Result<()> {
+    let elapsed = current_time.saturating_sub(last_performance_update);
+    require!(elapsed >= min_update_interval as i64, RebalancerError::UpdateTooFrequent);
     Ok(())
 }
 
@@ -66,39 +163,26 @@ pub fn calculate_performance_score(
     balance: u64,         // Current capital allocated in lamports
     volatility: u32,      // Risk score 0-10000 (100.00% max)
 ) -> Result<u64> {
-    // NORMALIZATION TO 0-10000 SCALE FOR EACH METRIC
-    
-    // Normalize yield rate: 0-50000 basis points -> 0-10000 scale
-    let normalized_yield = if yield_rate > 50000 {
-        10000u64
-    } else {
-        (yield_rate as u128 * 10000u128 / 50000u128) as u64
-    };
-    
-    // Normalize balance: Use logarithmic scaling for better distribution
-    // Range: 100M lamports (0.1 SOL) to 100B lamports (100 SOL) -> 0-10000 scale
-    let normalized_balance = if balance == 0 {
-        0u64
-    } else if balance >= 100_000_000_000u64 { // 100 SOL cap
-        10000u64
-    } else if balance < 100_000_000u64 { // 0.1 SOL minimum
-        (balance as u128 * 1000u128 / 100_000_000u128) as u64 // Linear below minimum
-    } else {
-        // Logarithmic scaling between 0.1 and 100 SOL
-        let log_balance = ((balance as f64).ln() * 1000.0) as u64;
-        let log_min = ((100_000_000f64).ln() * 1000.0) as u64;
-        let log_max = ((100_000_000_000f64).ln() * 1000.0) as u64;
-        
-        if log_max > log_min {
-            ((log_balance.saturating_sub(log_min) as u128 * 10000u128) / 
-             (log_max - log_min) as u128) as u64
-        } else {
-            5000u64 // Fallback to median if calculation fails
-        }
-    };
+    calculate_performance_score_with_config(yield_rate, balance, volatility, &PortfolioConfig::default())
+}
+
+// Same scoring with the yield ceiling and balance band taken from the portfolio config
+pub fn calculate_performance_score_with_config(
+    yield_rate: u64,
+    balance: u64,
+    volatility: u32,
+    config: &PortfolioConfig,
+) -> Result<u64> {
+    let balance_floor = config.balance_floor_lamports;  // Bottom of the log band (linear below)
+    let balance_cap = config.balance_cap_lamports;      // Balance receiving the full balance component
+    let yield_ceiling = config.yield_ceiling_bps as u64; // Yield receiving the full yield component
+    require!(balance_floor > 0 && balance_floor < balance_cap, RebalancerError::InvalidBalanceBand);
+    require!(yield_ceiling > 0, RebalancerError::InvalidAllocationPercentage);
     
-    // Normalize inverse volatility: 0-10000 volatility -> 10000-0 inverse scale
-    let normalized_inverse_volatility = 10000u32.saturating_sub(volatility.min(10000)) as u64;
+    // NORMALIZATION TO 0-10000 SCALE FOR EACH METRIC
+    let normalized_yield = normalize_yield(yield_rate, yield_ceiling);
+    let normalized_balance = normalize_balance(balance, config)?;
+    let normalized_inverse_volatility = normalize_inverse_volatility(volatility, config.volatility_floor);
     
     // WEIGHTED COMPOSITE CALCULATION: Yield(45%) + Balance(35%) + InverseVolatility(20%)
     let yield_component = normalized_yield
@@ -129,9 +213,236 @@ pub fn calculate_performance_score(
     Ok(performance_score)
 }
 
+// Yield rate: 0-yield_ceiling basis points (default 50000) -> 0-10000 scale
+pub(crate) fn normalize_yield(yield_rate: u64, yield_ceiling: u64) -> u64 {
+    if yield_rate > yield_ceiling {
+        10000u64
+    } else {
+        (yield_rate as u128 * 10000u128 / yield_ceiling as u128) as u64
+    }
+}
+
+// Balance: the config's curve over balance_floor to balance_cap (default 0.1 - 100 SOL) -> 0-10000 scale
+pub(crate) fn normalize_balance(balance: u64, config: &PortfolioConfig) -> Result<u64> {
+    normalize_balance_with_scaling(
+        balance,
+        config.balance_floor_lamports,
+        config.balance_cap_lamports,
+        config.balance_scaling,
+    )
+}
+
+// Inverse volatility: 0-10000 volatility -> 10000-0 inverse scale
+// (raised to the configured floor first - no strategy is truly riskless)
+pub(crate) fn normalize_inverse_volatility(volatility: u32, volatility_floor: u32) -> u64 {
+    VolatilityScore::saturating_from_bps(volatility.max(volatility_floor)).inverse_bps() as u64
+}
+
+// BALANCE NORMALIZATION (0-10000)
+// Linear below the floor, logarithmic between floor and cap, full score at the cap.
+// Rejects any non-finite log or out-of-range result instead of letting a float
+// cast silently produce 0 or a saturated value.
+pub fn normalize_balance_component(
+    balance: u64,
+    balance_floor: u64,
+    balance_cap: u64,
+) -> Result<u64> {
+    if balance == 0 {
+        return Ok(0);
+    }
+    if balance >= balance_cap {
+        return Ok(10000);
+    }
+    if balance < balance_floor {
+        return Ok((balance as u128 * 1000u128 / balance_floor as u128) as u64); // Linear below minimum
+    }
+    
+    // Logarithmic scaling between floor and cap
+    let scaled_ln = |value: u64| -> Result<u64> {
+        let scaled = (value as f64).ln() * 1000.0;
+        require!(scaled.is_finite() && scaled >= 0.0, RebalancerError::MathOverflow);
+        Ok(scaled as u64)
+    };
+    let log_balance = scaled_ln(balance)?;
+    let log_min = scaled_ln(balance_floor)?;
+    let log_max = scaled_ln(balance_cap)?;
+    
+    if log_max <= log_min {
+        return Ok(5000); // Fallback to median if calculation fails
+    }
+    
+    // floor <= balance < cap, so log_min <= log_balance <= log_max
+    require!(log_balance >= log_min && log_balance <= log_max, RebalancerError::MathOverflow);
+    let normalized = ((log_balance - log_min) as u128 * 10000u128 / (log_max - log_min) as u128) as u64;
+    require!(normalized <= 10000, RebalancerError::MathOverflow);
+    
+    Ok(normalized)
+}
+
+// BALANCE NORMALIZATION WITH A SELECTABLE CURVE (0-10000)
+// Same treatment below the floor and at the cap as the logarithmic curve; between
+// them SquareRoot and Linear use integer math only, so every validator agrees:
+//   SquareRoot: (isqrt(balance) - isqrt(floor)) / (isqrt(cap) - isqrt(floor))
+//   Linear:     (balance - floor) / (cap - floor)
+pub fn normalize_balance_with_scaling(
+    balance: u64,
+    balance_floor: u64,
+    balance_cap: u64,
+    scaling: BalanceScaling,
+) -> Result<u64> {
+    if scaling == BalanceScaling::Logarithmic || balance < balance_floor || balance >= balance_cap {
+        return normalize_balance_component(balance, balance_floor, balance_cap);
+    }
+    
+    let (position, span) = match scaling {
+        BalanceScaling::SquareRoot => {
+            let root_floor = integer_sqrt(balance_floor);
+            (integer_sqrt(balance) - root_floor, integer_sqrt(balance_cap) - root_floor)
+        },
+        _ => (balance - balance_floor, balance_cap - balance_floor),
+    };
+    require!(span > 0, RebalancerError::InvalidBalanceBand);
+    
+    let normalized = (position as u128 * 10000u128 / span as u128) as u64;
+    require!(normalized <= 10000, RebalancerError::MathOverflow);
+    
+    Ok(normalized)
+}
+
+// Floor of the square root via Newton's method
+pub fn integer_sqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    
+    let value = value as u128;
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x as u64
+}
+
+// SIGNED-YIELD SCORING
+// A loss contributes no yield component and is penalized at the yield weight (45%)
+// of its normalized magnitude, so a -20% strategy scores below a 0% one
+pub fn calculate_signed_performance_score(
+    yield_rate_signed: i64,   // Annual yield in basis points (-10000 to 50000)
+    balance: u64,
+    volatility: u32,
+    config: &PortfolioConfig,
+) -> Result<u64> {
+    let score = |yield_rate: u64| calculate_performance_score_with_config(yield_rate, balance, volatility, config);
+    
+    if yield_rate_signed >= 0 {
+        return score(yield_rate_signed as u64);
+    }
+    
+    let base_score = score(0)?;
+    
+    // Normalize loss: 0 to -10000 bps -> 0-10000 scale
+    let normalized_loss = yield_rate_signed.unsigned_abs().min(10000);
+    let loss_penalty = normalized_loss
+        .checked_mul(4500)
+        .ok_or(RebalancerError::BalanceOverflow)?
+        / 10000;
+    
+    Ok(base_score.saturating_sub(loss_penalty))
+}
+
+// REWARD-VS-RISK ADJUSTMENT (YieldFarming)
+// With a coupling configured, a farm's positive yield is scaled by its reward_multiplier
+// and its volatility grows by coupling_bps per extra multiple:
+//   volatility * (10000 + coupling_bps * (multiplier - 1)) / 10000, capped at 10000
+// At 10000 bps the risk grows in proportion to the reward, so leverage only pays
+// off for farms whose underlying volatility is low.
+pub fn reward_adjusted_metrics(
+    protocol_type: &ProtocolType,
+    yield_rate_signed: i64,
+    volatility: u32,
+    config: &PortfolioConfig,
+) -> (i64, u32) {
+    let multiplier = match protocol_type {
+        ProtocolType::YieldFarming { reward_multiplier, .. } if config.reward_risk_coupling_bps > 0 => {
+            (*reward_multiplier).max(1) as u64
+        }
+        _ => return (yield_rate_signed, volatility),
+    };
+    
+    let adjusted_yield = if yield_rate_signed > 0 {
+        yield_rate_signed.saturating_mul(multiplier as i64)
+    } else {
+        yield_rate_signed
+    };
+    
+    let risk_factor = 10000 + config.reward_risk_coupling_bps as u64 * (multiplier - 1);
+    let adjusted_volatility = (volatility as u64 * risk_factor / 10000).min(10000) as u32;
+    
+    (adjusted_yield, adjusted_volatility)
+}
+
+// Scores a strategy from its stored metrics, including any reward-vs-risk adjustment
+pub fn score_strategy(strategy: &Strategy, config: &PortfolioConfig) -> Result<u64> {
+    let (yield_rate, volatility) = reward_adjusted_metrics(
+        &strategy.protocol_type,
+        strategy.effective_yield_rate(),
+        strategy.volatility_score,
+        config,
+    );
+    
+    calculate_signed_performance_score(yield_rate, strategy.current_balance, volatility, config)
+}
+
+// EXPONENTIAL MOVING AVERAGE OF PERFORMANCE SCORES
+// smoothed = (alpha * current + (10000 - alpha) * previous) / 10000
+pub fn calculate_smoothed_score(
+    previous: u64,
+    current: u64,
+    alpha_bps: u64,
+) -> Result<u64> {
+    require!(alpha_bps <= 10000, RebalancerError::InvalidAllocationPercentage);
+    
+    let weighted_current = (current as u128)
+        .checked_mul(alpha_bps as u128)
+        .ok_or(RebalancerError::MathOverflow)?;
+    let weighted_previous = (previous as u128)
+        .checked_mul((10000 - alpha_bps) as u128)
+        .ok_or(RebalancerError::MathOverflow)?;
+    
+    let smoothed = weighted_current
+        .checked_add(weighted_previous)
+        .ok_or(RebalancerError::MathOverflow)?
+        / 10000u128;
+    
+    u64::try_from(smoothed).map_err(|_| RebalancerError::MathOverflow.into())
+}
+
+// BALANCE-ONLY RESCORING (deposits and withdrawals)
+// Yield and volatility are kept; the balance component moves with the new balance,
+// and the rank is flagged stale until the next ranking cycle
+pub fn apply_balance_change(
+    strategy: &mut Strategy,
+    new_balance: u64,
+    config: &PortfolioConfig,
+) -> Result<()> {
+    Strategy::validate_balance_update(new_balance)?;
+    
+    strategy.current_balance = new_balance;
+    strategy.performance_score = score_strategy(strategy, config)?;
+    if !config.smooth_scores {
+        strategy.smoothed_score = strategy.performance_score;
+    }
+    strategy.set_rank_stale(true);
+    
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fixtures;
     
     #[test]
     fn test_performance_score_calculation() {
@@ -165,12 +476,443 @@ mod tests {
         // Total = 900 + 0 + 1000 = 1900
         assert_eq!(score_zero, 1900);
         
-        // Maximum values - perfect score
+        // Maximum values - perfect score except the 1% volatility floor (20 points)
         let score_max = calculate_performance_score(50000, 100_000_000_000, 0).unwrap();
-        assert_eq!(score_max, 10000); // Perfect score
+        assert_eq!(score_max, 9980);
         
         // Minimum values  
         let score_min = calculate_performance_score(0, 100_000_000, 10000).unwrap();
         assert!(score_min < 5000); // Low score as expected
     }
+    
+    #[test]
+    fn test_volatility_floor_caps_zero_volatility_bonus() {
+        let unfloored = PortfolioConfig { volatility_floor: 0, ..PortfolioConfig::default() };
+        let floored = PortfolioConfig::default(); // 100 = 1%
+        
+        // Without the floor a zero-volatility strategy collects the full 2000-point component
+        let before = calculate_performance_score_with_config(50000, 100_000_000_000, 0, &unfloored).unwrap();
+        assert_eq!(before, 10000);
+        
+        // With it, zero volatility scores exactly like 1% volatility
+        let after = calculate_performance_score_with_config(50000, 100_000_000_000, 0, &floored).unwrap();
+        let at_floor = calculate_performance_score_with_config(50000, 100_000_000_000, 100, &floored).unwrap();
+        assert_eq!(after, 9980); // 9900 inverse volatility -> 1980
+        assert_eq!(after, at_floor);
+        
+        // Volatility above the floor is unaffected
+        assert_eq!(
+            calculate_performance_score_with_config(50000, 100_000_000_000, 3000, &floored).unwrap(),
+            calculate_performance_score_with_config(50000, 100_000_000_000, 3000, &unfloored).unwrap()
+        );
+    }
+    
+    #[test]
+    fn test_balance_component_just_above_floor() {
+        let floor = crate::constants::DEFAULT_BALANCE_FLOOR_LAMPORTS; // 0.1 SOL
+        let cap = crate::constants::DEFAULT_BALANCE_CAP_LAMPORTS;
+        assert_eq!(floor, 100_000_000);
+        
+        assert_eq!(normalize_balance_component(floor, floor, cap).unwrap(), 0);
+        
+        // Immediately above the floor: small, nonnegative, never wrapped
+        let mut previous = 0;
+        for balance in [floor + 1, floor + 10, floor + 1_000, floor + 100_000, floor + 1_000_000] {
+            let normalized = normalize_balance_component(balance, floor, cap).unwrap();
+            assert!(normalized < 100, "balance {} normalized to {}", balance, normalized);
+            assert!(normalized >= previous);
+            previous = normalized;
+        }
+        
+        // Just below the cap stays in range; the cap itself is the full score
+        assert!(normalize_balance_component(cap - 1, floor, cap).unwrap() <= 10000);
+        assert_eq!(normalize_balance_component(cap, floor, cap).unwrap(), 10000);
+        assert_eq!(normalize_balance_component(u64::MAX, floor, cap).unwrap(), 10000);
+    }
+    
+    #[test]
+    fn test_negative_yield_scores_below_zero_yield() {
+        let balance = 5_000_000_000u64; // 5 SOL
+        let volatility = 3000u32;
+        let config = PortfolioConfig::default();
+        
+        let zero_yield = calculate_signed_performance_score(0, balance, volatility, &config).unwrap();
+        let losing = calculate_signed_performance_score(-2000, balance, volatility, &config).unwrap(); // -20%
+        
+        assert!(losing < zero_yield);
+        assert_eq!(zero_yield - losing, 900); // 2000/10000 loss * 45% weight
+        
+        // Positive yields score exactly as before
+        assert_eq!(
+            calculate_signed_performance_score(15000, balance, volatility, &config).unwrap(),
+            calculate_performance_score(15000, balance, volatility).unwrap()
+        );
+    }
+    
+    #[test]
+    fn test_quote_normalized_balances_score_consistently() {
+        let strategy_with_quote = |quote_mint: Pubkey, quote_decimals: u8| Strategy {
+            total_deposits: 0,
+            quote_mint,
+            quote_decimals,
+            ..test_fixtures::strategy()
+        };
+        
+        let sol_strategy = strategy_with_quote(Pubkey::default(), 9);
+        let usdc_strategy = strategy_with_quote(Pubkey::new_unique(), 6);
+        
+        // 5 SOL vs 1000 USDC at 200 USDC/SOL (5_000_000 lamports per USDC)
+        let sol_balance = sol_strategy.normalize_balance(5_000_000_000, 0).unwrap();
+        let usdc_balance = usdc_strategy.normalize_balance(1_000_000_000, 5_000_000).unwrap();
+        assert_eq!(sol_balance, usdc_balance);
+        
+        // Same value, same yield and risk -> same score
+        assert_eq!(
+            calculate_performance_score(8000, sol_balance, 2000).unwrap(),
+            calculate_performance_score(8000, usdc_balance, 2000).unwrap()
+        );
+        
+        // Raw USDC units would have been scored as 1 SOL
+        assert!(calculate_performance_score(8000, 1_000_000_000, 2000).unwrap()
+            < calculate_performance_score(8000, usdc_balance, 2000).unwrap());
+        
+        // Non-SOL strategies need a price
+        assert!(usdc_strategy.normalize_balance(1_000_000_000, 0).is_err());
+    }
+    
+    #[test]
+    fn test_configured_balance_band_separates_large_strategies() {
+        const SOL: u64 = 1_000_000_000;
+        let large_balances = [2_000 * SOL, 5_000 * SOL, 9_000 * SOL];
+        
+        // Default 0.1-100 SOL band saturates: every large strategy gets the same score
+        let default_scores: Vec<u64> = large_balances
+            .iter()
+            .map(|&b| calculate_performance_score(8000, b, 2000).unwrap())
+            .collect();
+        assert!(default_scores.windows(2).all(|w| w[0] == w[1]));
+        
+        // 1000-10000 SOL band spreads them out
+        let config = PortfolioConfig {
+            balance_floor_lamports: 1_000 * SOL,
+            balance_cap_lamports: 10_000 * SOL,
+            ..PortfolioConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        let banded_scores: Vec<u64> = large_balances
+            .iter()
+            .map(|&b| calculate_signed_performance_score(8000, b, 2000, &config).unwrap())
+            .collect();
+        assert!(banded_scores[0] < banded_scores[1] && banded_scores[1] < banded_scores[2]);
+        
+        // Inverted band is rejected
+        let inverted = PortfolioConfig {
+            balance_floor_lamports: 10_000 * SOL,
+            balance_cap_lamports: 1_000 * SOL,
+            ..PortfolioConfig::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+    
+    #[test]
+    fn test_configured_yield_ceiling_spreads_normal_yields() {
+        let balance = 5_000_000_000u64;
+        let volatility = 3000u32;
+        
+        // Default 500% ceiling: 10% vs 20% yield barely differ
+        let default_gap = calculate_performance_score(2000, balance, volatility).unwrap()
+            - calculate_performance_score(1000, balance, volatility).unwrap();
+        
+        // 20% ceiling: the same yields span half the yield component
+        let config = PortfolioConfig {
+            yield_ceiling_bps: 2000,
+            ..PortfolioConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        let low = calculate_performance_score_with_config(1000, balance, volatility, &config).unwrap();
+        let high = calculate_performance_score_with_config(2000, balance, volatility, &config).unwrap();
+        
+        assert_eq!(default_gap, 90);
+        assert_eq!(high - low, 2250);
+        
+        // Yields above the ceiling saturate
+        let above = calculate_performance_score_with_config(4000, balance, volatility, &config).unwrap();
+        assert_eq!(above, high);
+        
+        let zero_ceiling = PortfolioConfig {
+            yield_ceiling_bps: 0,
+            ..PortfolioConfig::default()
+        };
+        assert!(zero_ceiling.validate().is_err());
+    }
+    
+    #[test]
+    fn test_update_interval_rate_limit() {
+        let min_update_interval = 300u32; // 5 minutes
+        let first_update = 1_000i64;
+        
+        // Second update 10s later is rejected
+        assert!(check_update_interval(first_update, first_update + 10, min_update_interval).is_err());
+        
+        // Update once the interval has elapsed succeeds
+        assert!(check_update_interval(first_update, first_update + 300, min_update_interval).is_ok());
+        
+        // Disabled by default
+        assert!(check_update_interval(first_update, first_update, 0).is_ok());
+    }
+    
+    #[test]
+    fn test_reward_multiplier_pays_only_at_low_volatility() {
+        let config = PortfolioConfig { reward_risk_coupling_bps: 10000, ..PortfolioConfig::default() };
+        let farm = |reward_multiplier: u8| ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 300,
+        };
+        let score = |protocol: &ProtocolType, volatility: u32, config: &PortfolioConfig| {
+            let (yield_rate, volatility) = reward_adjusted_metrics(protocol, 2000, volatility, config);
+            calculate_signed_performance_score(yield_rate, 10_000_000_000, volatility, config).unwrap()
+        };
+        let (levered, plain) = (farm(5), farm(1));
+        
+        // Proportional coupling: 5x the reward carries 5x the volatility
+        assert_eq!(reward_adjusted_metrics(&levered, 2000, 500, &config), (10000, 2500));
+        assert_eq!(reward_adjusted_metrics(&plain, 2000, 500, &config), (2000, 500));
+        
+        // Low underlying volatility: the boosted yield outweighs the added risk
+        assert!(score(&levered, 500, &config) > score(&plain, 500, &config));
+        
+        // High underlying volatility: the added risk outweighs the boosted yield
+        assert!(score(&levered, 3000, &config) < score(&plain, 3000, &config));
+        
+        // Off by default - the multiplier is ignored entirely
+        let off = PortfolioConfig::default();
+        assert_eq!(score(&levered, 3000, &off), score(&plain, 3000, &off));
+        
+        let stable = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        assert_eq!(reward_adjusted_metrics(&stable, 2000, 500, &config), (2000, 500));
+    }
+    
+    #[test]
+    fn test_balance_scaling_curves() {
+        let sol = 1_000_000_000u64;
+        let (floor, cap) = (sol / 10, 1000 * sol);
+        let curve = |scaling: BalanceScaling| -> Vec<u64> {
+            [sol, 10 * sol, 100 * sol]
+                .iter()
+                .map(|&balance| normalize_balance_with_scaling(balance, floor, cap, scaling).unwrap())
+                .collect()
+        };
+        
+        let log = curve(BalanceScaling::Logarithmic);
+        let sqrt = curve(BalanceScaling::SquareRoot);
+        let linear = curve(BalanceScaling::Linear);
+        assert_eq!(log, vec![2500, 4999, 7499]);
+        assert_eq!(sqrt, vec![218, 909, 3093]);
+        assert_eq!(linear, vec![9, 99, 999]);
+        
+        // Each curve is strictly increasing, and log > sqrt > linear at every point
+        for values in [&log, &sqrt, &linear] {
+            assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        for i in 0..3 {
+            assert!(log[i] > sqrt[i] && sqrt[i] > linear[i]);
+        }
+        
+        // All curves agree at the band edges
+        for scaling in [BalanceScaling::Logarithmic, BalanceScaling::SquareRoot, BalanceScaling::Linear] {
+            assert_eq!(normalize_balance_with_scaling(floor, floor, cap, scaling).unwrap(), 0);
+            assert_eq!(normalize_balance_with_scaling(cap, floor, cap, scaling).unwrap(), 10000);
+        }
+        
+        // Newton's method lands on the exact floor of the root
+        assert_eq!(integer_sqrt(99), 9);
+        assert_eq!(integer_sqrt(100), 10);
+        assert_eq!(integer_sqrt(u64::MAX), u32::MAX as u64);
+    }
+    
+    #[test]
+    fn test_smoothed_score_dampens_spike() {
+        use crate::instructions::execute_ranking::{calculate_percentile_rankings, StrategyData};
+        
+        let steady_score = 6000u64;
+        let spiking_previous = 5000u64;
+        let spike = 8000u64;
+        
+        // One-cycle spike only moves the smoothed score 30% of the way
+        let smoothed = calculate_smoothed_score(
+            spiking_previous, spike, crate::constants::SCORE_EMA_ALPHA_BPS
+        ).unwrap();
+        assert_eq!(smoothed, 5900);
+        assert!(smoothed > spiking_previous && smoothed < spike);
+        
+        let steady_id = Pubkey::new_unique();
+        let spiking_id = Pubkey::new_unique();
+        let build = |spiking_score: u64| vec![
+            StrategyData {
+                strategy_id: steady_id,
+                performance_score: steady_score,
+                current_balance: 1_000_000_000,
+                volatility_score: 3000,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            },
+            StrategyData {
+                strategy_id: spiking_id,
+                performance_score: spiking_score,
+                current_balance: 1_000_000_000,
+                volatility_score: 3000,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            },
+        ];
+        
+        // Raw spike would flip the ranking; the smoothed score does not
+        let mut raw = build(spike);
+        calculate_percentile_rankings(&mut raw).unwrap();
+        assert_eq!(raw[0].strategy_id, spiking_id);
+        
+        let mut smoothed_ranking = build(smoothed);
+        calculate_percentile_rankings(&mut smoothed_ranking).unwrap();
+        assert_eq!(smoothed_ranking[0].strategy_id, steady_id);
+    }
+    
+    #[test]
+    fn test_volatility_score_units_agree() {
+        let from_percent = VolatilityScore::from_percent(50).unwrap();
+        let from_bps = VolatilityScore::from_bps(5000).unwrap();
+        assert_eq!(from_percent, from_bps);
+        assert_eq!(from_percent.bps(), 5000);
+        assert_eq!(from_bps.percent(), 50);
+        assert_eq!(from_bps.inverse_bps(), 5000);
+        
+        // Bounds on both scales
+        assert_eq!(VolatilityScore::from_percent(100).unwrap(), VolatilityScore::from_bps(10000).unwrap());
+        assert!(VolatilityScore::from_percent(101).is_err());
+        assert!(VolatilityScore::from_bps(10001).is_err());
+        assert_eq!(VolatilityScore::saturating_from_bps(u32::MAX).bps(), VolatilityScore::MAX_BPS);
+        
+        // Scoring reads the bps scale: 50% volatility earns half the volatility component
+        let config = PortfolioConfig { volatility_floor: 0, ..PortfolioConfig::default() };
+        let calm = calculate_performance_score_with_config(0, 0, 0, &config).unwrap();
+        let half = calculate_performance_score_with_config(0, 0, from_percent.bps(), &config).unwrap();
+        assert_eq!(calm - half, 1000);
+    }
+    
+    #[test]
+    fn test_performance_update_writes_supplied_timestamp() {
+        let mut strategy = Strategy {
+            last_updated: 1_000,
+            last_performance_update: 1_000,
+            total_deposits: 0,
+            ..test_fixtures::strategy()
+        };
+        let config = PortfolioConfig { min_update_interval: 600, ..PortfolioConfig::default() };
+        
+        apply_performance_update(&mut strategy, &config, 8000, 2000, 5_000_000_000, true, 1_600).unwrap();
+        assert_eq!(strategy.last_updated, 1_600);
+        assert_eq!(strategy.yield_rate, 8000);
+        assert_eq!(strategy.volatility_score, 2000);
+        assert_eq!(strategy.performance_score, calculate_performance_score(8000, 5_000_000_000, 2000).unwrap());
+        
+        // Rate limit is measured against the timestamp just written
+        assert_eq!(strategy.last_performance_update, 1_600);
+        assert!(apply_performance_update(&mut strategy, &config, 9000, 2000, 5_000_000_000, true, 2_199).is_err());
+        assert_eq!(strategy.last_updated, 1_600);
+        assert_eq!(strategy.yield_rate, 8000);
+        
+        // Forced updates skip the interval but still stamp the supplied time
+        apply_performance_update(&mut strategy, &config, -500, 2000, 5_000_000_000, false, 1_700).unwrap();
+        assert_eq!(strategy.last_updated, 1_700);
+        assert_eq!(strategy.yield_rate, 0);
+        assert_eq!(strategy.yield_rate_signed, -500);
+        
+        // Invalid input leaves the strategy untouched
+        assert!(apply_performance_update(&mut strategy, &config, 8000, 10_001, 5_000_000_000, false, 1_800).is_err());
+        assert_eq!(strategy.last_updated, 1_700);
+    }
+    
+    #[test]
+    fn test_normalize_yield() {
+        let ceiling = crate::constants::DEFAULT_YIELD_CEILING_BPS as u64;
+        assert_eq!(normalize_yield(0, ceiling), 0);
+        assert_eq!(normalize_yield(5000, ceiling), 1000);   // 50% APY
+        assert_eq!(normalize_yield(25000, ceiling), 5000);
+        assert_eq!(normalize_yield(ceiling, ceiling), 10000);
+        assert_eq!(normalize_yield(ceiling + 1, ceiling), 10000);
+        assert_eq!(normalize_yield(u64::MAX, ceiling), 10000);
+        
+        // A lower ceiling saturates sooner
+        assert_eq!(normalize_yield(1000, 2000), 5000);
+    }
+    
+    #[test]
+    fn test_normalize_balance_band_boundaries() {
+        let config = PortfolioConfig::default();
+        let floor = config.balance_floor_lamports; // 0.1 SOL
+        let cap = config.balance_cap_lamports;     // 100 SOL
+        assert_eq!((floor, cap), (100_000_000, 100_000_000_000));
+        
+        // Below 0.1 SOL: linear up to 1000; the log band restarts at 0 on the floor itself
+        assert_eq!(normalize_balance(0, &config).unwrap(), 0);
+        assert_eq!(normalize_balance(floor / 2, &config).unwrap(), 500);
+        assert_eq!(normalize_balance(floor - 1, &config).unwrap(), 999);
+        assert_eq!(normalize_balance(floor, &config).unwrap(), 0);
+        
+        // Inside the band: ~1 SOL is a third of the way through three decades
+        let one_sol = normalize_balance(1_000_000_000, &config).unwrap();
+        assert!((3300..=3340).contains(&one_sol), "1 SOL normalized to {}", one_sol);
+        
+        // 100 SOL and above: full score
+        assert!(normalize_balance(cap - 1, &config).unwrap() <= 10000);
+        assert_eq!(normalize_balance(cap, &config).unwrap(), 10000);
+        assert_eq!(normalize_balance(cap * 10, &config).unwrap(), 10000);
+        
+        // Every curve shares the boundaries
+        for balance_scaling in [BalanceScaling::SquareRoot, BalanceScaling::Linear] {
+            let curved = PortfolioConfig { balance_scaling, ..config };
+            assert_eq!(normalize_balance(floor, &curved).unwrap(), 0);
+            assert_eq!(normalize_balance(floor - 1, &curved).unwrap(), 999);
+            assert_eq!(normalize_balance(cap, &curved).unwrap(), 10000);
+        }
+    }
+    
+    #[test]
+    fn test_normalize_inverse_volatility() {
+        assert_eq!(normalize_inverse_volatility(0, 0), 10000);
+        assert_eq!(normalize_inverse_volatility(3000, 0), 7000);
+        assert_eq!(normalize_inverse_volatility(10000, 0), 0);
+        assert_eq!(normalize_inverse_volatility(u32::MAX, 0), 0);
+        
+        // The floor lifts low volatility before inverting; higher readings pass through
+        let floor = crate::constants::DEFAULT_VOLATILITY_FLOOR;
+        assert_eq!(normalize_inverse_volatility(0, floor), 9900);
+        assert_eq!(normalize_inverse_volatility(3000, floor), 7000);
+    }
+    
+    #[test]
+    fn test_ranking_does_not_reset_update_interval() {
+        use crate::instructions::execute_ranking::rank_strategy_batch;
+        
+        let config = PortfolioConfig { min_update_interval: 600, ..PortfolioConfig::default() };
+        let mut portfolio = Portfolio { config, ..test_fixtures::portfolio() };
+        let mut a = test_fixtures::scored_strategy(&config, 9000, 1_000);
+        let mut b = test_fixtures::scored_strategy(&config, 5000, 1_000);
+        let mut c = test_fixtures::scored_strategy(&config, 1000, 1_000);
+        
+        // Ranked just before the oracle's next scheduled update
+        rank_strategy_batch(&mut portfolio, &mut [&mut a, &mut b, &mut c], 1_590).unwrap();
+        assert_eq!(a.last_updated, 1_590);
+        
+        // 600s after the previous performance update, 10s after the ranking
+        apply_performance_update(&mut a, &config, 8000, 3000, 1_000_000_000, true, 1_600).unwrap();
+        assert_eq!(a.last_performance_update, 1_600);
+        assert!(apply_performance_update(&mut a, &config, 8000, 3000, 1_000_000_000, true, 2_199).is_err());
+    }
 }
@@ -0,0 +1,446 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+
+// Upper bound on positions touched per call (account + compute limits)
+pub const MAX_POSITION_BATCH: usize = 10;
+
+// Token prices are lamports per token unit with 6 decimals (matches entry_price_a/b)
+pub const POSITION_PRICE_SCALE: u128 = 1_000_000;
+
+#[derive(Accounts)]
+pub struct UpdatePositionsBatch<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    // remaining_accounts: one writable CapitalPosition per price update, same order
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct OpenPosition<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    #[account(
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ RebalancerError::StrategyNotFound
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = CapitalPosition::MAX_SIZE,
+        seeds = [b"position", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump
+    )]
+    pub position: Account<'info, CapitalPosition>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct ClosePosition<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+        has_one = manager @ RebalancerError::InvalidManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+
+    // Every lamport in the account (the rent recorded in rent_paid plus any excess)
+    // goes back to the manager when the account closes
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"position", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = position.bump,
+        constraint = position.strategy_id == strategy_id @ RebalancerError::InvalidPosition
+    )]
+    pub position: Account<'info, CapitalPosition>,
+
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+pub fn open_position(
+    ctx: Context<OpenPosition>,
+    strategy_id: Pubkey,
+    position_type: PositionType,
+) -> Result<()> {
+    let rent_paid = ctx.accounts.position.to_account_info().lamports();
+    let position = &mut ctx.accounts.position;
+
+    init_position(
+        position,
+        strategy_id,
+        position_type,
+        rent_paid,
+        ctx.bumps.position,
+        Clock::get()?.unix_timestamp,
+    );
+
+    msg!("Position opened: strategy={}, rent_paid={}", strategy_id, rent_paid);
+
+    Ok(())
+}
+
+pub fn close_position(ctx: Context<ClosePosition>, strategy_id: Pubkey) -> Result<()> {
+    let account_lamports = ctx.accounts.position.to_account_info().lamports();
+    let closed = settle_position_close(&mut ctx.accounts.position, account_lamports);
+
+    msg!("Position closed: strategy={}, token_a={}, token_b={}, lp={}, rent_refunded={}",
+         strategy_id, closed.token_a_returned, closed.token_b_returned,
+         closed.lp_tokens_returned, closed.rent_refunded);
+
+    emit!(PositionClosedEvent {
+        strategy_id,
+        manager: ctx.accounts.manager.key(),
+        token_a_returned: closed.token_a_returned,
+        token_b_returned: closed.token_b_returned,
+        lp_tokens_returned: closed.lp_tokens_returned,
+        rent_refunded: closed.rent_refunded,
+        excess_lamports: closed.excess_lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn init_position(
+    position: &mut CapitalPosition,
+    strategy_id: Pubkey,
+    position_type: PositionType,
+    rent_paid: u64,
+    bump: u8,
+    current_time: i64,
+) {
+    position.strategy_id = strategy_id;
+    position.token_a_amount = 0;
+    position.token_b_amount = 0;
+    position.lp_tokens = 0;
+    position.platform_controlled_lp = 0;
+    position.position_type = position_type;
+    position.entry_price_a = 0;
+    position.entry_price_b = 0;
+    position.last_rebalance = current_time;
+    position.accrued_fees = 0;
+    position.impermanent_loss = 0;
+    position.bump = bump;
+    position.rent_paid = rent_paid;
+    position.reserved = [0; 7];
+}
+
+// What closing a position hands back to the manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedPosition {
+    pub token_a_returned: u64,
+    pub token_b_returned: u64,
+    pub lp_tokens_returned: u64,
+    pub rent_refunded: u64,
+    pub excess_lamports: u64,
+}
+
+// Zeroes the position and splits the account's lamports into the recorded rent
+// and anything deposited on top of it
+pub fn settle_position_close(position: &mut CapitalPosition, account_lamports: u64) -> ClosedPosition {
+    let rent_refunded = position.rent_paid.min(account_lamports);
+    let closed = ClosedPosition {
+        token_a_returned: position.token_a_amount,
+        token_b_returned: position.token_b_amount,
+        lp_tokens_returned: position.lp_tokens,
+        rent_refunded,
+        excess_lamports: account_lamports - rent_refunded,
+    };
+
+    position.token_a_amount = 0;
+    position.token_b_amount = 0;
+    position.lp_tokens = 0;
+    position.platform_controlled_lp = 0;
+    position.accrued_fees = 0;
+    position.impermanent_loss = 0;
+    position.rent_paid = 0;
+
+    closed
+}
+
+#[event]
+pub struct PositionClosedEvent {
+    pub strategy_id: Pubkey,
+    pub manager: Pubkey,
+    pub token_a_returned: u64,
+    pub token_b_returned: u64,
+    pub lp_tokens_returned: u64,
+    pub rent_refunded: u64,
+    pub excess_lamports: u64,
+    pub timestamp: i64,
+}
+
+pub fn update_positions_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdatePositionsBatch<'info>>,
+    price_updates: Vec<PriceUpdate>,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // BATCH SIZE VALIDATIONS
+    require!(!price_updates.is_empty(), RebalancerError::InsufficientStrategies);
+    require!(price_updates.len() <= MAX_POSITION_BATCH, RebalancerError::TooManyStrategies);
+    require!(
+        ctx.remaining_accounts.len() == price_updates.len(),
+        RebalancerError::InvalidPosition
+    );
+
+    for (update, account_info) in price_updates.iter().zip(ctx.remaining_accounts.iter()) {
+        require!(account_info.is_writable, RebalancerError::InvalidPosition);
+
+        let mut position: Account<'info, CapitalPosition> = Account::try_from(account_info)?;
+
+        // POSITION MUST BELONG TO THIS PORTFOLIO AND MATCH THE UPDATE
+        require!(position.strategy_id == update.strategy_id, RebalancerError::InvalidPosition);
+        let expected_key = Pubkey::create_program_address(
+            &[
+                b"position",
+                portfolio_key.as_ref(),
+                update.strategy_id.as_ref(),
+                &[position.bump],
+            ],
+            ctx.program_id,
+        ).map_err(|_| RebalancerError::InvalidPosition)?;
+        require!(expected_key == account_info.key(), RebalancerError::InvalidPosition);
+
+        apply_price_update(&mut position, update, current_time)?;
+        position.exit(ctx.program_id)?;
+
+        msg!("Position updated: strategy={}, price_a={}, price_b={}, IL={}%",
+             update.strategy_id, update.current_price_a, update.current_price_b, position.impermanent_loss);
+    }
+
+    msg!("Batch position update completed: {} positions", price_updates.len());
+
+    Ok(())
+}
+
+// PRICE UPDATE APPLICATION (IL RECOMPUTE + TIMESTAMP)
+pub fn apply_price_update(
+    position: &mut CapitalPosition,
+    update: &PriceUpdate,
+    current_time: i64,
+) -> Result<()> {
+    require!(update.current_price_a > 0, RebalancerError::InvalidAllocationPercentage);
+    require!(update.current_price_b > 0, RebalancerError::InvalidAllocationPercentage);
+
+    position.impermanent_loss = calculate_impermanent_loss(
+        position.entry_price_a,
+        position.entry_price_b,
+        update.current_price_a,
+        update.current_price_b,
+    )?;
+    position.last_rebalance = current_time;
+
+    Ok(())
+}
+
+// IMPERMANENT LOSS: % DEVIATION OF THE A/B PRICE RATIO FROM ENTRY
+pub fn calculate_impermanent_loss(
+    entry_price_a: u64,
+    entry_price_b: u64,
+    current_price_a: u64,
+    current_price_b: u64,
+) -> Result<i64> {
+    // Single-asset positions carry no pair exposure
+    if entry_price_a == 0 || entry_price_b == 0 {
+        return Ok(0);
+    }
+
+    let entry_ratio = (entry_price_a as u128 * 1_000_000u128)
+        .checked_div(entry_price_b as u128)
+        .ok_or(RebalancerError::DivisionByZero)?;
+    let current_ratio = (current_price_a as u128 * 1_000_000u128)
+        .checked_div(current_price_b as u128)
+        .ok_or(RebalancerError::DivisionByZero)?;
+
+    if entry_ratio == 0 {
+        return Ok(0);
+    }
+
+    let deviation = current_ratio.abs_diff(entry_ratio);
+    let il_percentage = deviation
+        .checked_mul(100)
+        .ok_or(RebalancerError::MathOverflow)?
+        / entry_ratio;
+
+    i64::try_from(il_percentage).map_err(|_| RebalancerError::MathOverflow.into())
+}
+
+// MULTI-ASSET POSITION VALUATION
+// Lamport-equivalent value of both legs at current prices, reduced by the tracked
+// impermanent loss (clamped to 0-100%) plus accrued fees; used as the balance input
+// to scoring for LiquidityPair positions
+pub fn position_value(position: &CapitalPosition, price_a: u64, price_b: u64) -> Result<u64> {
+    let value_a = (position.token_a_amount as u128)
+        .checked_mul(price_a as u128)
+        .ok_or(RebalancerError::MathOverflow)?
+        / POSITION_PRICE_SCALE;
+    let value_b = (position.token_b_amount as u128)
+        .checked_mul(price_b as u128)
+        .ok_or(RebalancerError::MathOverflow)?
+        / POSITION_PRICE_SCALE;
+    let gross_value = value_a
+        .checked_add(value_b)
+        .ok_or(RebalancerError::MathOverflow)?;
+
+    let il_percentage = position.impermanent_loss.clamp(0, 100) as u128;
+    let net_value = gross_value * (100 - il_percentage) / 100;
+
+    let total = net_value
+        .checked_add(position.accrued_fees as u128)
+        .ok_or(RebalancerError::MathOverflow)?;
+
+    u64::try_from(total).map_err(|_| RebalancerError::MathOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position_with_entry(entry_price_a: u64, entry_price_b: u64) -> CapitalPosition {
+        CapitalPosition {
+            strategy_id: Pubkey::new_unique(),
+            token_a_amount: 1_000_000_000,
+            token_b_amount: 1_000_000_000,
+            lp_tokens: 1_000_000,
+            platform_controlled_lp: 1_000_000,
+            position_type: PositionType::LiquidityPair,
+            entry_price_a,
+            entry_price_b,
+            last_rebalance: 0,
+            accrued_fees: 0,
+            impermanent_loss: 0,
+            bump: 255,
+            rent_paid: 0,
+            reserved: [0; 7],
+        }
+    }
+
+    #[test]
+    fn test_batch_price_updates_recompute_il() {
+        let mut position_1 = position_with_entry(100_000_000, 1_000_000); // A/B = 100
+        let mut position_2 = position_with_entry(20_000_000, 1_000_000);  // A/B = 20
+
+        let update_1 = PriceUpdate {
+            strategy_id: position_1.strategy_id,
+            current_price_a: 150_000_000, // A up 50%
+            current_price_b: 1_000_000,
+        };
+        let update_2 = PriceUpdate {
+            strategy_id: position_2.strategy_id,
+            current_price_a: 18_000_000, // A down 10%
+            current_price_b: 1_000_000,
+        };
+
+        apply_price_update(&mut position_1, &update_1, 1_000).unwrap();
+        apply_price_update(&mut position_2, &update_2, 2_000).unwrap();
+
+        assert_eq!(position_1.impermanent_loss, 50);
+        assert_eq!(position_2.impermanent_loss, 10);
+        assert_eq!(position_1.last_rebalance, 1_000);
+        assert_eq!(position_2.last_rebalance, 2_000);
+    }
+
+    #[test]
+    fn test_balanced_pair_position_value() {
+        // 1000 A + 1000 B at 1 lamport each, no price divergence
+        let mut position = position_with_entry(1_000_000, 1_000_000);
+        assert_eq!(position_value(&position, 1_000_000, 1_000_000).unwrap(), 2_000_000_000);
+
+        // Accrued fees add on top of both legs
+        position.accrued_fees = 5_000_000;
+        assert_eq!(position_value(&position, 1_000_000, 1_000_000).unwrap(), 2_005_000_000);
+
+        // Legs priced separately
+        assert_eq!(position_value(&position, 2_000_000, 500_000).unwrap(), 2_505_000_000);
+    }
+
+    #[test]
+    fn test_pair_position_value_net_of_impermanent_loss() {
+        let mut position = position_with_entry(1_000_000, 1_000_000);
+        position.accrued_fees = 10_000_000;
+        let update = PriceUpdate {
+            strategy_id: position.strategy_id,
+            current_price_a: 1_500_000, // A up 50% against B
+            current_price_b: 1_000_000,
+        };
+        apply_price_update(&mut position, &update, 1_000).unwrap();
+        assert_eq!(position.impermanent_loss, 50);
+
+        // Gross 1.5 + 1.0 SOL, halved by 50% IL, plus fees
+        assert_eq!(position_value(&position, 1_500_000, 1_000_000).unwrap(), 1_260_000_000);
+
+        // IL beyond 100% never drives the pair value negative
+        position.impermanent_loss = 250;
+        assert_eq!(position_value(&position, 1_500_000, 1_000_000).unwrap(), 10_000_000);
+    }
+
+    #[test]
+    fn test_price_update_rejects_zero_price() {
+        let mut position = position_with_entry(100_000_000, 1_000_000);
+        let update = PriceUpdate {
+            strategy_id: position.strategy_id,
+            current_price_a: 0,
+            current_price_b: 1_000_000,
+        };
+
+        assert!(apply_price_update(&mut position, &update, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_close_position_refunds_rent_and_returns_tokens() {
+        let rent = Rent::default().minimum_balance(CapitalPosition::MAX_SIZE);
+        let mut position = position_with_entry(0, 0);
+        let strategy_id = Pubkey::new_unique();
+
+        init_position(&mut position, strategy_id, PositionType::LiquidityPair, rent, 254, 1_000);
+        assert_eq!(position.rent_paid, rent);
+        assert_eq!((position.token_a_amount, position.lp_tokens), (0, 0));
+
+        // Capital flows in while the position is open
+        position.token_a_amount = 2_000_000_000;
+        position.token_b_amount = 500_000_000;
+        position.lp_tokens = 1_000_000;
+        position.platform_controlled_lp = 1_000_000;
+
+        let closed = settle_position_close(&mut position, rent + 5_000);
+        assert_eq!(closed, ClosedPosition {
+            token_a_returned: 2_000_000_000,
+            token_b_returned: 500_000_000,
+            lp_tokens_returned: 1_000_000,
+            rent_refunded: rent,
+            excess_lamports: 5_000,
+        });
+
+        // Nothing is left to account for
+        assert_eq!(position.token_a_amount + position.token_b_amount + position.lp_tokens, 0);
+        assert_eq!(position.rent_paid, 0);
+
+        // Field carved from the old reserved bytes still fits the existing account size
+        assert!(8 + position.try_to_vec().unwrap().len() <= CapitalPosition::MAX_SIZE);
+    }
+}
@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::*;
+use crate::constants::*;
+use super::redistribute_capital::*;
+
+#[derive(Accounts)]
+pub struct ValidateAllocationPlan<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.seed_manager.as_ref()],
+        bump = portfolio.bump,
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+// Dry run of redistribute_capital's checks; reports the first issue instead of failing
+pub fn validate_allocation_plan(
+    ctx: Context<ValidateAllocationPlan>,
+    allocations: Vec<CapitalAllocation>,
+) -> Result<()> {
+    let (issue_code, total) = check_allocation_plan(&allocations, &RiskLimits::default());
+
+    msg!("Allocation plan validation: valid={}, total={}, issue={}",
+         issue_code == PLAN_VALID, total, issue_code);
+
+    emit!(PlanValidationEvent {
+        portfolio: ctx.accounts.portfolio.key(),
+        valid: issue_code == PLAN_VALID,
+        total,
+        issue_code,
+    });
+
+    Ok(())
+}
+
+// Returns (PLAN_* issue code, plan total); total is 0 when the totals check itself fails
+pub fn check_allocation_plan(
+    allocations: &[CapitalAllocation],
+    risk_limits: &RiskLimits,
+) -> (u8, u64) {
+    if allocations.is_empty() || allocations.len() > MAX_REDISTRIBUTION_ALLOCATIONS {
+        return (PLAN_INVALID_BATCH_SIZE, 0);
+    }
+
+    let total = match validate_allocations(allocations) {
+        Ok(total) => total,
+        Err(error) if error == RebalancerError::DuplicateStrategy.into() => {
+            return (PLAN_DUPLICATE_STRATEGY, 0);
+        },
+        Err(_) => return (PLAN_INVALID_AMOUNT, 0),
+    };
+
+    // PER-STRATEGY BOUNDS (fees are not subject to diversification limits)
+    let max_single_allocation = (total as u128 * risk_limits.max_single_strategy_bps as u128 / 10000u128) as u64;
+    let min_single_allocation = (total as u128 * risk_limits.min_single_strategy_bps as u128 / 10000u128) as u64;
+
+    for allocation in allocations.iter().filter(|a| {
+        matches!(a.allocation_type, AllocationType::TopPerformer | AllocationType::RiskDiversification)
+    }) {
+        if allocation.amount > max_single_allocation {
+            return (PLAN_ABOVE_MAX_SINGLE_STRATEGY, total);
+        }
+        if allocation.amount < min_single_allocation {
+            return (PLAN_BELOW_MIN_SINGLE_STRATEGY, total);
+        }
+    }
+
+    (PLAN_VALID, total)
+}
+
+#[event]
+pub struct PlanValidationEvent {
+    pub portfolio: Pubkey,
+    pub valid: bool,
+    pub total: u64,
+    pub issue_code: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strategy_allocation(amount: u64) -> CapitalAllocation {
+        CapitalAllocation {
+            strategy_id: Pubkey::new_unique(),
+            amount,
+            allocation_type: AllocationType::TopPerformer,
+        }
+    }
+
+    #[test]
+    fn test_valid_plan() {
+        let plan = vec![
+            strategy_allocation(3_500_000_000),
+            strategy_allocation(3_500_000_000),
+            strategy_allocation(3_000_000_000),
+        ];
+        assert_eq!(check_allocation_plan(&plan, &RiskLimits::default()), (PLAN_VALID, 10_000_000_000));
+    }
+
+    #[test]
+    fn test_duplicate_strategy_plan() {
+        let first = strategy_allocation(3_000_000_000);
+        let duplicate = CapitalAllocation { amount: 2_000_000_000, ..first.clone() };
+        let plan = vec![first, duplicate, strategy_allocation(3_000_000_000)];
+
+        let (issue_code, _) = check_allocation_plan(&plan, &RiskLimits::default());
+        assert_eq!(issue_code, PLAN_DUPLICATE_STRATEGY);
+    }
+
+    #[test]
+    fn test_validation_order_for_oversized_duplicated_batch() {
+        let repeated = strategy_allocation(1_000_000_000);
+        let with_duplicates = |len: usize| -> Vec<CapitalAllocation> {
+            (0..len).map(|i| if i % 2 == 0 { repeated.clone() } else { strategy_allocation(1_000_000_000) }).collect()
+        };
+
+        // Oversized and duplicated: the size check wins
+        let err = validate_allocations(&with_duplicates(25)).unwrap_err();
+        assert_eq!(err, RebalancerError::TooManyStrategies.into());
+        let (issue_code, _) = check_allocation_plan(&with_duplicates(25), &RiskLimits::default());
+        assert_eq!(issue_code, PLAN_INVALID_BATCH_SIZE);
+
+        // At the size limit the duplicate is reported
+        let err = validate_allocations(&with_duplicates(MAX_REDISTRIBUTION_ALLOCATIONS)).unwrap_err();
+        assert_eq!(err, RebalancerError::DuplicateStrategy.into());
+
+        // Duplicate is checked before the amount of the same entry
+        let mut plan = vec![repeated.clone(), CapitalAllocation { amount: 0, ..repeated.clone() }];
+        let err = validate_allocations(&plan).unwrap_err();
+        assert_eq!(err, RebalancerError::DuplicateStrategy.into());
+
+        // Earlier entries are fully checked before later ones
+        plan.insert(0, strategy_allocation(0));
+        let err = validate_allocations(&plan).unwrap_err();
+        assert_eq!(err, RebalancerError::ZeroAllocationAmount.into());
+
+        let err = validate_allocations(&[]).unwrap_err();
+        assert_eq!(err, RebalancerError::InsufficientStrategies.into());
+    }
+
+    #[test]
+    fn test_over_cap_plan() {
+        // 60% to a single strategy exceeds the 40% default cap
+        let plan = vec![strategy_allocation(6_000_000_000), strategy_allocation(4_000_000_000)];
+
+        let (issue_code, total) = check_allocation_plan(&plan, &RiskLimits::default());
+        assert_eq!(issue_code, PLAN_ABOVE_MAX_SINGLE_STRATEGY);
+        assert_eq!(total, 10_000_000_000);
+    }
+
+    #[test]
+    fn test_zero_amount_allocation_is_malformed_not_short() {
+        let plan = vec![strategy_allocation(3_000_000_000), strategy_allocation(0)];
+
+        let err = validate_allocations(&plan).unwrap_err();
+        assert_eq!(err, RebalancerError::ZeroAllocationAmount.into());
+        assert_ne!(err, RebalancerError::InsufficientBalance.into());
+
+        let (issue_code, total) = check_allocation_plan(&plan, &RiskLimits::default());
+        assert_eq!(issue_code, PLAN_INVALID_AMOUNT);
+        assert_eq!(total, 0);
+    }
+}
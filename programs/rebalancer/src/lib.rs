@@ -6,6 +6,10 @@ pub mod state;
 pub mod instructions;
 pub mod errors;
 pub mod constants;
+pub mod profile;
+
+#[cfg(test)]
+mod test_fixtures;
 
 use instructions::*;
 use state::*;
@@ -19,8 +23,9 @@ pub mod rebalancer {
         manager: Pubkey,
         rebalance_threshold: u8,
         min_rebalance_interval: i64,
+        base_currency: [u8; 8],
     ) -> Result<()> {
-        instructions::initialize_portfolio(ctx, manager, rebalance_threshold, min_rebalance_interval)
+        instructions::initialize_portfolio(ctx, manager, rebalance_threshold, min_rebalance_interval, base_currency)
     }
     
     pub fn register_strategy(
@@ -64,8 +69,315 @@ pub mod rebalancer {
     pub fn redistribute_capital(
         ctx: Context<RedistributeCapital>, 
         allocations: Vec<CapitalAllocation>,
+        available_capital: u64,
+    ) -> Result<()> {
+        instructions::redistribute_capital(ctx, allocations, available_capital)
+    }
+    
+    pub fn abandon_allocation_plan(ctx: Context<AbandonAllocationPlan>) -> Result<()> {
+        instructions::abandon_allocation_plan(ctx)
+    }
+    
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        strategy_id: Pubkey,
+        position_type: PositionType,
+    ) -> Result<()> {
+        instructions::open_position(ctx, strategy_id, position_type)
+    }
+    
+    pub fn close_position(ctx: Context<ClosePosition>, strategy_id: Pubkey) -> Result<()> {
+        instructions::close_position(ctx, strategy_id)
+    }
+    
+    pub fn update_positions_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdatePositionsBatch<'info>>,
+        price_updates: Vec<PriceUpdate>,
+    ) -> Result<()> {
+        instructions::update_positions_batch(ctx, price_updates)
+    }
+    
+    pub fn update_portfolio_config(
+        ctx: Context<UpdatePortfolioConfig>,
+        config: PortfolioConfig,
+    ) -> Result<()> {
+        instructions::update_portfolio_config(ctx, config)
+    }
+    
+    pub fn set_performance_fee(
+        ctx: Context<UpdatePortfolioConfig>,
+        performance_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::set_performance_fee(ctx, performance_fee_bps)
+    }
+    
+    pub fn bulk_deprecate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+        rank_cutoff: u8,
+    ) -> Result<()> {
+        instructions::bulk_deprecate(ctx, rank_cutoff)
+    }
+    
+    pub fn flag_for_deprecation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+    ) -> Result<()> {
+        instructions::flag_for_deprecation(ctx)
+    }
+    
+    pub fn pause_all_strategies<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+    ) -> Result<()> {
+        instructions::pause_all_strategies(ctx)
+    }
+    
+    pub fn resume_all_strategies<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkStrategyStatus<'info>>,
+    ) -> Result<()> {
+        instructions::resume_all_strategies(ctx)
+    }
+    
+    pub fn status_breakdown<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StatusBreakdown<'info>>,
+    ) -> Result<()> {
+        instructions::status_breakdown(ctx)
+    }
+    
+    pub fn set_target_weight(
+        ctx: Context<ConfigureStrategy>,
+        strategy_id: Pubkey,
+        target_weight_bps: u16,
+    ) -> Result<()> {
+        instructions::set_target_weight(ctx, strategy_id, target_weight_bps)
+    }
+    
+    pub fn repair_strategy_count<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RepairStrategyCount<'info>>,
+        verified_count: u32,
+    ) -> Result<()> {
+        instructions::repair_strategy_count(ctx, verified_count)
+    }
+    
+    pub fn is_rebalanceable(ctx: Context<IsRebalanceable>) -> Result<()> {
+        instructions::is_rebalanceable(ctx)
+    }
+    
+    pub fn update_performance_signed(
+        ctx: Context<UpdatePerformance>,
+        strategy_id: Pubkey,
+        yield_rate_signed: i64,
+        volatility_score: u32,
+        current_balance: u64,
+    ) -> Result<()> {
+        instructions::update_performance_signed(ctx, strategy_id, yield_rate_signed, volatility_score, current_balance)
+    }
+    
+    pub fn set_reinvest_rewards(
+        ctx: Context<ManageRewards>,
+        strategy_id: Pubkey,
+        reinvest_rewards: bool,
+    ) -> Result<()> {
+        instructions::set_reinvest_rewards(ctx, strategy_id, reinvest_rewards)
+    }
+    
+    pub fn accrue_rewards(
+        ctx: Context<ManageRewards>,
+        strategy_id: Pubkey,
+        reward_amount: u64,
+    ) -> Result<()> {
+        instructions::accrue_rewards(ctx, strategy_id, reward_amount)
+    }
+    
+    pub fn update_protocol_params(
+        ctx: Context<ConfigureStrategy>,
+        strategy_id: Pubkey,
+        protocol_type: ProtocolType,
+    ) -> Result<()> {
+        instructions::update_protocol_params(ctx, strategy_id, protocol_type)
+    }
+    
+    pub fn migrate_protocol_type(
+        ctx: Context<ConfigureStrategy>,
+        strategy_id: Pubkey,
+        new_protocol_type: ProtocolType,
+    ) -> Result<()> {
+        instructions::migrate_protocol_type(ctx, strategy_id, new_protocol_type)
+    }
+    
+    pub fn validate_allocation_plan(
+        ctx: Context<ValidateAllocationPlan>,
+        allocations: Vec<CapitalAllocation>,
+    ) -> Result<()> {
+        instructions::validate_allocation_plan(ctx, allocations)
+    }
+    
+    pub fn set_strategy_pinned(
+        ctx: Context<ConfigureStrategy>,
+        strategy_id: Pubkey,
+        pinned: bool,
+    ) -> Result<()> {
+        instructions::set_strategy_pinned(ctx, strategy_id, pinned)
+    }
+    
+    pub fn is_stale<'info>(
+        ctx: Context<'_, '_, 'info, 'info, IsStale<'info>>,
     ) -> Result<()> {
-        instructions::redistribute_capital(ctx, allocations)
+        instructions::is_stale(ctx)
+    }
+    
+    pub fn emit_staleness<'info>(
+        ctx: Context<'_, '_, 'info, 'info, EmitStaleness<'info>>,
+        staleness_threshold: i64,
+    ) -> Result<()> {
+        instructions::emit_staleness(ctx, staleness_threshold)
+    }
+    
+    pub fn emit_strategy_snapshots<'info>(
+        ctx: Context<'_, '_, 'info, 'info, EmitStrategySnapshots<'info>>,
+    ) -> Result<()> {
+        instructions::emit_strategy_snapshots(ctx)
+    }
+    
+    pub fn portfolio_summary<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PortfolioSummary<'info>>,
+    ) -> Result<()> {
+        instructions::portfolio_summary(ctx)
+    }
+    
+    pub fn set_quote_currency(
+        ctx: Context<ConfigureStrategy>,
+        strategy_id: Pubkey,
+        quote_mint: Pubkey,
+        quote_decimals: u8,
+    ) -> Result<()> {
+        instructions::set_quote_currency(ctx, strategy_id, quote_mint, quote_decimals)
+    }
+    
+    pub fn update_performance_quoted(
+        ctx: Context<UpdatePerformance>,
+        strategy_id: Pubkey,
+        yield_rate: u64,
+        volatility_score: u32,
+        quote_balance: u64,
+        quote_price_lamports: u64,
+    ) -> Result<()> {
+        instructions::update_performance_quoted(ctx, strategy_id, yield_rate, volatility_score, quote_balance, quote_price_lamports)
+    }
+    
+    pub fn add_authorized_treasury(
+        ctx: Context<ManageTreasuryWhitelist>,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::add_authorized_treasury(ctx, treasury)
+    }
+    
+    pub fn remove_authorized_treasury(
+        ctx: Context<ManageTreasuryWhitelist>,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::remove_authorized_treasury(ctx, treasury)
+    }
+    
+    pub fn collect_performance_fee(
+        ctx: Context<ConfigureStrategy>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::collect_performance_fee(ctx, strategy_id)
+    }
+    
+    pub fn set_guardians(
+        ctx: Context<SetGuardians>,
+        guardians: [Pubkey; 5],
+        guardian_threshold: u8,
+    ) -> Result<()> {
+        instructions::set_guardians(ctx, guardians, guardian_threshold)
+    }
+    
+    pub fn guardian_vote(
+        ctx: Context<GuardianVote>,
+        freeze: bool,
+    ) -> Result<()> {
+        instructions::guardian_vote(ctx, freeze)
+    }
+    
+    pub fn deposit_to_strategy(
+        ctx: Context<DepositToStrategy>,
+        strategy_id: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::deposit_to_strategy(ctx, strategy_id, amount)
+    }
+    
+    pub fn withdraw_from_strategy(
+        ctx: Context<WithdrawFromStrategy>,
+        strategy_id: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::withdraw_from_strategy(ctx, strategy_id, amount)
+    }
+    
+    pub fn init_registry_page(
+        ctx: Context<InitRegistryPage>,
+        page: u32,
+    ) -> Result<()> {
+        instructions::init_registry_page(ctx, page)
+    }
+    
+    pub fn deregister_strategy(
+        ctx: Context<DeregisterStrategy>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::deregister_strategy(ctx, strategy_id)
+    }
+    
+    pub fn init_deposit_queue(
+        ctx: Context<InitDepositQueue>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::init_deposit_queue(ctx, strategy_id)
+    }
+    
+    pub fn process_deposit_queue(
+        ctx: Context<ProcessDepositQueue>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::process_deposit_queue(ctx, strategy_id)
+    }
+    
+    pub fn cancel_queued_deposit(
+        ctx: Context<CancelQueuedDeposit>,
+        strategy_id: Pubkey,
+        index: u8,
+    ) -> Result<()> {
+        instructions::cancel_queued_deposit(ctx, strategy_id, index)
+    }
+    
+    pub fn init_summary_pack(ctx: Context<InitSummaryPack>) -> Result<()> {
+        instructions::init_summary_pack(ctx)
+    }
+    
+    pub fn sync_summary_pack<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateSummaryPack<'info>>,
+    ) -> Result<()> {
+        instructions::sync_summary_pack(ctx)
+    }
+    
+    pub fn rank_summary_pack(ctx: Context<RankSummaryPack>) -> Result<()> {
+        instructions::rank_summary_pack(ctx)
+    }
+    
+    pub fn propose_manager_transfer(
+        ctx: Context<ManagerTransfer>,
+        new_manager: Pubkey,
+    ) -> Result<()> {
+        instructions::propose_manager_transfer(ctx, new_manager)
+    }
+    
+    pub fn cancel_manager_transfer(ctx: Context<ManagerTransfer>) -> Result<()> {
+        instructions::cancel_manager_transfer(ctx)
+    }
+    
+    pub fn accept_manager_transfer(ctx: Context<AcceptManagerTransfer>) -> Result<()> {
+        instructions::accept_manager_transfer(ctx)
     }
     
     // Legacy initialize function for backward compatibility
@@ -0,0 +1,52 @@
+// COMPUTE-UNIT PROFILING
+// Logs remaining compute units at instrumented points; compiled out unless the
+// `profile` feature is enabled
+#[macro_export]
+macro_rules! profile_cu {
+    ($label:expr) => {
+        #[cfg(feature = "profile")]
+        {
+            anchor_lang::prelude::msg!("CU profile: {}", $label);
+            anchor_lang::solana_program::log::sol_log_compute_units();
+        }
+    };
+}
+
+#[cfg(all(test, feature = "profile"))]
+mod tests {
+    use anchor_lang::prelude::*;
+    use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::instructions::execute_ranking::{calculate_percentile_rankings, StrategyData};
+
+    static COMPUTE_UNIT_LOGS: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingStubs;
+
+    impl SyscallStubs for CountingStubs {
+        fn sol_log_compute_units(&self) {
+            COMPUTE_UNIT_LOGS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_profiling_logs_compute_units() {
+        set_syscall_stubs(Box::new(CountingStubs));
+        let before = COMPUTE_UNIT_LOGS.load(Ordering::SeqCst);
+
+        let mut strategies: Vec<StrategyData> = (0..3u64)
+            .map(|i| StrategyData {
+                strategy_id: Pubkey::new_unique(),
+                performance_score: 1000 * (i + 1),
+                current_balance: 1_000_000_000,
+                volatility_score: 3000,
+                percentile_rank: 0,
+                rebalance_threshold: 25,
+            })
+            .collect();
+        calculate_percentile_rankings(&mut strategies).unwrap();
+
+        // Start and end of calculate_percentile_rankings
+        assert!(COMPUTE_UNIT_LOGS.load(Ordering::SeqCst) >= before + 2);
+    }
+}
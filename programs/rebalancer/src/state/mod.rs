@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+pub mod reserved_layout;
+
 #[account]
 #[derive(Debug)]
 pub struct Portfolio {
@@ -12,10 +14,112 @@ pub struct Portfolio {
     pub portfolio_creation: i64,            // 8 bytes - Portfolio creation timestamp
     pub emergency_pause: bool,              // 1 byte - Emergency stop flag
     pub performance_fee_bps: u16,           // 2 bytes - Performance fee in basis points
+    pub ranking_cycle_count: u64,           // 8 bytes - Lifetime ranking cycles executed
+    pub guardians: [Pubkey; 5],             // 160 bytes - Emergency freeze guardians (default = empty slot)
+    pub guardian_threshold: u8,             // 1 byte - Approvals needed to freeze/unfreeze (0 = no guardians)
+    pub guardian_set_version: u8,           // 1 byte - Bumped on guardian changes; voids pending approvals
+    pub config: PortfolioConfig,            // PortfolioConfig::SIZE bytes - Manager-tunable parameters
     pub bump: u8,                           // 1 byte - PDA bump seed
-    pub reserved: [u8; 31],                 // 31 bytes - Future expansion buffer
+    pub total_aum: u64,                     // 8 bytes - Capital deposited into strategies (lamports)
+    pub base_currency: [u8; 8],             // 8 bytes - Reporting currency label, ASCII zero-padded (e.g. "SOL")
+    pub scoring_epoch: u32,                 // 4 bytes - Bumped whenever a config change alters the scoring formula
+    pub seed_manager: Pubkey,               // 32 bytes - Manager at creation; seeds the portfolio PDA across manager transfers
+    pub pending_manager: Pubkey,            // 32 bytes - Proposed next manager awaiting acceptance (default = none)
+    pub reserved: [u8; 23],                 // 23 bytes - Future expansion buffer
+}
+// Total: 382 bytes + PortfolioConfig::SIZE
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PortfolioConfig {
+    pub threshold_floor: u8,                // 1 byte - Dynamic threshold minimum (1-50)
+    pub threshold_ceiling: u8,              // 1 byte - Dynamic threshold maximum (1-50)
+    pub require_funded_registration: bool,  // 1 byte - Registration must transfer initial_balance
+    pub smooth_scores: bool,                // 1 byte - Rank on EMA-smoothed performance score
+    pub extraction_order: ExtractionOrder,  // 1 byte - Priority of underperformers for extraction
+    pub max_extractions: u8,                // 1 byte - Max accounts extracted per rebalance (1-10)
+    pub allocation_strategy: AllocationStrategy, // 1 byte - Capital allocation algorithm
+    pub min_update_interval: u32,           // 4 bytes - Minimum seconds between performance updates (0 = off)
+    pub max_reward_multiplier: u8,          // 1 byte - YieldFarming reward_multiplier cap (1-10)
+    pub stale_window: u32,                  // 4 bytes - Seconds without rebalance before a portfolio may be stale
+    pub cash_buffer_bps: u16,               // 2 bytes - Share of capital withheld as cash reserve (0-5000)
+    pub balance_floor_lamports: u64,        // 8 bytes - Balance scoring band lower bound
+    pub balance_cap_lamports: u64,          // 8 bytes - Balance scoring band upper bound (full score)
+    pub yield_ceiling_bps: u32,             // 4 bytes - Yield receiving the full yield score (1-50000)
+    pub rank_hysteresis: u8,                // 1 byte - Percentile band around the threshold (0 = off)
+    pub warmup_window: u32,                 // 4 bytes - Seconds after creation a strategy is never extracted
+    pub target_aum_cap: u64,                // 8 bytes - Maximum total_aum (0 = uncapped)
+    pub aum_cap_soft: bool,                 // 1 byte - Warn instead of rejecting deposits past the cap
+    pub score_check: ScoreCheckMode,        // 1 byte - Re-derive stored scores before ranking
+    pub tie_break: TieBreak,                // 1 byte - Secondary sort keys for equal scores
+    pub extraction_cooldown: u32,           // 4 bytes - Seconds an extracted strategy is skipped for new capital
+    pub safe_harbor_strategy: Option<Pubkey>, // 33 bytes - StableLending strategy receiving capital in extreme volatility
+    pub safe_harbor_volatility: u8,         // 1 byte - Average volatility % above which the safe harbor engages (1-100)
+    pub absorb_skipped_shares: bool,        // 1 byte - Re-weight sub-minimum allocation shares onto funded strategies
+    pub volatility_floor: u32,              // 4 bytes - Minimum volatility assumed when scoring (0-10000)
+    pub fee_disposition: FeeDisposition,    // 1 byte - Pay fees out or reinvest them
+    pub fee_treasury_strategy: Pubkey,      // 32 bytes - Strategy credited with reinvested fees
+    pub pause_grace: u32,                   // 4 bytes - Seconds until an emergency pause auto-expires (0 = indefinite)
+    pub strategy_deposit_cap: u64,          // 8 bytes - Maximum balance per strategy before deposits queue (0 = uncapped)
+    pub reward_risk_coupling_bps: u16,      // 2 bytes - Volatility added per extra YieldFarming reward multiple (0 = off, 0-20000)
+    pub min_allocation_lamports: u64,       // 8 bytes - Absolute minimum allocation per strategy (0 = off)
+    pub min_funded_after_rebalance: u8,     // 1 byte - Strategies that must stay funded after extraction (0 = off)
+    pub emergency_cooldown: u32,            // 4 bytes - Seconds between emergency rebalances (< min_rebalance_interval)
+    pub balance_scaling: BalanceScaling,    // 1 byte - Balance normalization curve between floor and cap
+    pub max_metric_staleness: u32,          // 4 bytes - Seconds without an update before ranking auto-pauses a strategy (0 = off)
+    pub deprecation_rank_cutoff: u8,        // 1 byte - Percentile counted as a low rank for deprecation (0 = off, 1-100)
+    pub deprecation_low_rank_cycles: u8,    // 1 byte - Consecutive low ranks before flag_for_deprecation deprecates
+    pub min_score_for_allocation: u16,      // 2 bytes - Performance score below which a strategy gets no capital (0 = off, 0-10000)
+    pub concentration_bps: u16,             // 2 bytes - Performance vs equal weighting in allocation (0 = equal, 10000 = performance)
+    pub stale_epoch_policy: StaleEpochPolicy, // 1 byte - Ranking treatment of strategies scored under an older scoring_epoch
+    pub protocol_priority: [u8; 3],         // 3 bytes - Allocation order per ProtocolKind, higher funded first (all equal = score order)
+    pub drain_paused: bool,                 // 1 byte - Paused underperformers may be extracted from (never funded)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BalanceScaling {
+    Logarithmic,  // Strong diminishing returns - compresses large strategies
+    SquareRoot,   // Moderate diminishing returns
+    Linear,       // Proportional to balance
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum FeeDisposition {
+    Transfer,  // Platform/manager fees leave the portfolio as lamports
+    Reinvest,  // Fees are credited to fee_treasury_strategy's balance
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TieBreak {
+    BalanceFirst,     // Higher balance, then lower volatility
+    VolatilityFirst,  // Lower volatility, then higher balance - risk-averse managers
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ScoreCheckMode {
+    Off,     // Rank on stored scores as-is
+    Warn,    // Emit ScoreMismatchEvent when a stored score no longer matches its metrics
+    Strict,  // Reject the ranking batch on any mismatch
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum StaleEpochPolicy {
+    Exclude,  // Left out of ranking until a performance update re-scores it
+    Include,  // Ranked on its old score; still reported as needing a re-score
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AllocationStrategy {
+    PerformanceWeighted,  // Proportional to performance score with risk adjustment
+    TargetWeight,         // Per-strategy target_weight_bps of post-fee capital
+    EqualWeight,          // Even split across eligible strategies
+    RiskParity,           // Inversely proportional to volatility_score (equal risk contribution)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ExtractionOrder {
+    WorstFirst,    // Lowest percentile rank first - cut losers fastest
+    LargestFirst,  // Largest balance first - fewest accounts touched
 }
-// Total: 136 bytes
 
 #[account]
 #[derive(Debug)]
@@ -26,16 +130,29 @@ pub struct Strategy {
     pub yield_rate: u64,                    // 8 bytes - Annual yield in basis points (0-50000)
     pub volatility_score: u32,              // 4 bytes - Risk metric (0-10000, 100.00% max)
     pub performance_score: u64,             // 8 bytes - Calculated composite score
+    pub smoothed_score: u64,                // 8 bytes - EMA of performance_score (ranking input when enabled)
     pub percentile_rank: u8,                // 1 byte - 0-100 ranking position
     pub last_updated: i64,                  // 8 bytes - Last metric update timestamp
     pub status: StrategyStatus,             // 1 byte - Current strategy status
     pub total_deposits: u64,                // 8 bytes - Lifetime deposits tracking
     pub total_withdrawals: u64,             // 8 bytes - Lifetime withdrawals tracking
     pub creation_time: i64,                 // 8 bytes - Strategy creation timestamp
+    pub target_weight_bps: u16,             // 2 bytes - Target share of capital (TargetWeight mode)
+    pub quote_mint: Pubkey,                 // 32 bytes - Balance denomination (default = native SOL)
+    pub quote_decimals: u8,                 // 1 byte - Decimals of the quote denomination
     pub bump: u8,                           // 1 byte - PDA bump seed
-    pub reserved: [u8; 23],                 // 23 bytes - Future expansion
+    pub yield_rate_signed: i64,             // 8 bytes - Signed annual yield in bps (negative = loss)
+    pub pinned: bool,                       // 1 byte - Never rebalanced (e.g. locked positions)
+    pub reinvest_rewards: bool,             // 1 byte - Compound accrued rewards into current_balance
+    pub pending_rewards: u64,               // 8 bytes - Accrued rewards held for withdrawal
+    pub awaiting_recovery: bool,            // 1 byte - Rebalanced; rank must clear threshold + hysteresis
+    pub high_water_mark: u64,               // 8 bytes - Peak balance performance fees were last charged at
+    pub extraction_cooldown_until: i64,     // 8 bytes - No new capital before this time after an extraction
+    pub scoring_epoch: u32,                 // 4 bytes - Portfolio scoring_epoch performance_score was computed under
+    pub last_performance_update: i64,       // 8 bytes - Last oracle metric update (ranking does not touch it)
+    pub reserved: [u8; 4],                  // 4 bytes - Future expansion
 }
-// Total: ~144 bytes + protocol_type size
+// Total: 197 bytes + protocol_type size (max 100 bytes incl. variant tag)
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
 pub enum ProtocolType {
@@ -59,6 +176,14 @@ pub enum ProtocolType {
     },  // 70 bytes total
 }
 
+// Data-less protocol kind; compact u8 form for client filters and per-protocol limits
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProtocolKind {
+    StableLending,   // 0
+    YieldFarming,    // 1
+    LiquidStaking,   // 2
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum StrategyStatus {
     Active,      // Normal operation, participates in rebalancing
@@ -81,10 +206,31 @@ pub struct CapitalPosition {
     pub accrued_fees: u64,                  // 8 bytes - Accumulated fees in position
     pub impermanent_loss: i64,              // 8 bytes - IL tracking (can be negative)
     pub bump: u8,                           // 1 byte - PDA bump seed
-    pub reserved: [u8; 15],                 // 15 bytes - Future expansion
+    pub rent_paid: u64,                     // 8 bytes - Lamports deposited for rent at open, refunded on close
+    pub reserved: [u8; 7],                  // 7 bytes - Future expansion
 }
 // Total: 145 bytes
 
+#[account]
+#[derive(Debug)]
+pub struct CreationRateLimit {
+    pub authority: Pubkey,                  // 32 bytes - Payer being rate limited
+    pub window_start: i64,                  // 8 bytes - Start of the current creation window
+    pub count: u8,                          // 1 byte - Portfolios created in the current window
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 42 bytes
+
+#[account]
+#[derive(Debug)]
+pub struct TreasuryWhitelist {
+    pub portfolio: Pubkey,                  // 32 bytes - Portfolio this whitelist governs
+    pub treasuries: [Pubkey; 4],            // 128 bytes - Authorized fee destinations
+    pub count: u8,                          // 1 byte - Occupied slots in treasuries
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 162 bytes
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
 pub enum PositionType {
     SingleAsset,
@@ -93,41 +239,528 @@ pub enum PositionType {
 }
 
 impl Portfolio {
-    pub const MAX_SIZE: usize = 8 + 136;
+    pub const MAX_SIZE: usize = 8 + 382 + PortfolioConfig::SIZE;
     
     pub fn validate_rebalance_threshold(threshold: u8) -> Result<()> {
+        if !(1..=50).contains(&threshold) {
+            msg!(&out_of_range_log("rebalance threshold", threshold, "1-50"));
+        }
         require!((1..=50).contains(&threshold), crate::errors::RebalancerError::InvalidRebalanceThreshold);
         Ok(())
     }
     
+    // Emergency pause in effect; a time-boxed pause lapses at pause_expires_at
+    pub fn is_paused(&self, current_time: i64) -> bool {
+        let expires_at = self.pause_expires_at();
+        self.emergency_pause && (expires_at == 0 || current_time < expires_at)
+    }
+    
+    // Sets or clears the pause; a non-zero grace time-boxes it
+    pub fn set_emergency_pause(&mut self, paused: bool, current_time: i64) {
+        self.emergency_pause = paused;
+        let expires_at = if paused && self.config.pause_grace > 0 {
+            current_time.saturating_add(self.config.pause_grace as i64)
+        } else {
+            0
+        };
+        self.set_pause_expires_at(expires_at);
+    }
+    
     pub fn can_rebalance(&self, current_time: i64) -> bool {
-        !self.emergency_pause && 
+        !self.is_paused(current_time) && 
         current_time >= self.last_rebalance.saturating_add(self.min_rebalance_interval)
     }
     
+    // Gated ranking cycle: advances the cycle counter and opens the cycle. The
+    // cooldown is only consumed by complete_ranking_cycle, so re-running while a
+    // cycle is open is a no-op; returns whether a new cycle was opened
+    pub fn record_ranking_cycle(&mut self, current_time: i64) -> Result<bool> {
+        if self.ranking_cycle_open() {
+            return Ok(false);
+        }
+        self.require_rebalanceable(current_time)?;
+        
+        self.ranking_cycle_count = self.ranking_cycle_count
+            .checked_add(1)
+            .ok_or(crate::errors::RebalancerError::MathOverflow)?;
+        self.set_ranking_cycle_open(true);
+        
+        Ok(true)
+    }
+    
+    // Batch ranking ran: the open cycle (if any) now consumes the rebalance interval
+    pub fn complete_ranking_cycle(&mut self, current_time: i64) {
+        if self.ranking_cycle_open() {
+            self.last_rebalance = current_time;
+            self.set_ranking_cycle_open(false);
+        }
+    }
+    
+    // RANKING LOCK
+    // Holds the ranking_in_progress flag for the duration of `body` and clears it
+    // on every exit, Ok or Err, so no early return can leave the portfolio locked.
+    // Code that sets the flag must go through here rather than set it directly.
+    pub fn with_ranking_lock<T>(
+        &mut self,
+        body: impl FnOnce(&mut Portfolio) -> Result<T>,
+    ) -> Result<T> {
+        require!(!self.ranking_in_progress(), crate::errors::RebalancerError::RankingInProgress);
+        self.set_ranking_in_progress(true);
+        let result = body(self);
+        self.set_ranking_in_progress(false);
+        result
+    }
+    
+    // Single source of truth for rebalance gating; returns a REBALANCE_* reason code
+    pub fn rebalance_eligibility(&self, current_time: i64) -> u8 {
+        if self.is_paused(current_time) {
+            crate::constants::REBALANCE_BLOCKED_PAUSED
+        } else if !self.can_rebalance(current_time) {
+            crate::constants::REBALANCE_BLOCKED_INTERVAL
+        } else if self.total_strategies < 2 {
+            crate::constants::REBALANCE_BLOCKED_TOO_FEW_STRATEGIES
+        } else {
+            crate::constants::REBALANCE_ELIGIBLE
+        }
+    }
+    
+    pub fn require_rebalanceable(&self, current_time: i64) -> Result<()> {
+        use crate::errors::RebalancerError;
+        match self.rebalance_eligibility(current_time) {
+            crate::constants::REBALANCE_BLOCKED_PAUSED => err!(RebalancerError::EmergencyPauseActive),
+            crate::constants::REBALANCE_BLOCKED_INTERVAL => err!(RebalancerError::RebalanceIntervalNotMet),
+            crate::constants::REBALANCE_BLOCKED_TOO_FEW_STRATEGIES => err!(RebalancerError::InsufficientStrategies),
+            _ => Ok(()),
+        }
+    }
+    
+    // Stale: no rebalance within the configured window and nothing left active
+    pub fn is_stale(&self, current_time: i64, active_strategies: u32) -> bool {
+        let age_seconds = current_time.saturating_sub(self.last_rebalance);
+        age_seconds >= self.config.stale_window as i64 && active_strategies == 0
+    }
+    
+    // Returns the AUM after the deposit and whether it exceeds a soft cap;
+    // a hard cap rejects the deposit instead
+    pub fn check_aum_deposit(&self, amount: u64) -> Result<(u64, bool)> {
+        let new_total = self.total_aum
+            .checked_add(amount)
+            .ok_or(crate::errors::RebalancerError::BalanceOverflow)?;
+        let cap = self.config.target_aum_cap;
+        let over_cap = cap > 0 && new_total > cap;
+        
+        require!(!over_cap || self.config.aum_cap_soft, crate::errors::RebalancerError::AumCapExceeded);
+        Ok((new_total, over_cap))
+    }
+    
+    // Non-empty uppercase ASCII letters/digits, left-aligned and zero-padded
+    pub fn validate_base_currency(label: &[u8; 8]) -> Result<()> {
+        let len = label.iter().position(|&b| b == 0).unwrap_or(label.len());
+        let valid = len > 0
+            && label[..len].iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+            && label[len..].iter().all(|&b| b == 0);
+        if !valid {
+            msg!(&out_of_range_log("base currency", format!("{:?}", label), "1-8 uppercase ASCII letters/digits"));
+        }
+        require!(valid, crate::errors::RebalancerError::InvalidBaseCurrency);
+        Ok(())
+    }
+    
+    pub fn validate_performance_fee(bps: u16) -> Result<()> {
+        if bps > crate::constants::MAX_PERFORMANCE_FEE_BPS {
+            msg!(&out_of_range_log("performance fee", bps, "0-3000bps"));
+        }
+        require!(
+            bps <= crate::constants::MAX_PERFORMANCE_FEE_BPS,
+            crate::errors::RebalancerError::InvalidPerformanceFee
+        );
+        Ok(())
+    }
+    
+    pub fn guardian_index(&self, key: &Pubkey) -> Option<usize> {
+        if *key == Pubkey::default() {
+            return None;
+        }
+        self.guardians.iter().position(|g| g == key)
+    }
+    
+    // Guardians must be distinct; threshold is M-of-N over the non-empty slots
+    pub fn validate_guardians(guardians: &[Pubkey; 5], threshold: u8) -> Result<()> {
+        let mut members: Vec<&Pubkey> = guardians.iter().filter(|g| **g != Pubkey::default()).collect();
+        let count = members.len();
+        members.sort();
+        members.dedup();
+        require!(members.len() == count, crate::errors::RebalancerError::InvalidGuardian);
+        require!(
+            (count == 0 && threshold == 0) || (threshold >= 1 && threshold as usize <= count),
+            crate::errors::RebalancerError::InvalidGuardianThreshold
+        );
+        Ok(())
+    }
+    
+    // Two-step handover: the current manager proposes, the proposed key accepts
+    pub fn propose_manager_transfer(&mut self, new_manager: Pubkey) -> Result<()> {
+        require!(
+            new_manager != Pubkey::default() && new_manager != self.manager,
+            crate::errors::RebalancerError::InvalidManager
+        );
+        self.pending_manager = new_manager;
+        Ok(())
+    }
+    
+    // Clears the pending slot, returning the key that can no longer accept
+    pub fn cancel_manager_transfer(&mut self) -> Result<Pubkey> {
+        require!(
+            self.pending_manager != Pubkey::default(),
+            crate::errors::RebalancerError::NoPendingManagerTransfer
+        );
+        Ok(std::mem::take(&mut self.pending_manager))
+    }
+    
+    // Installs the pending manager, returning the previous one
+    pub fn accept_manager_transfer(&mut self, signer: &Pubkey) -> Result<Pubkey> {
+        require!(
+            self.pending_manager != Pubkey::default(),
+            crate::errors::RebalancerError::NoPendingManagerTransfer
+        );
+        require!(*signer == self.pending_manager, crate::errors::RebalancerError::InvalidManager);
+        let previous = std::mem::replace(&mut self.manager, *signer);
+        self.pending_manager = Pubkey::default();
+        Ok(previous)
+    }
+    
+    // Drops a partially applied plan so a different one can be submitted,
+    // returning how many allocations had already been applied
+    pub fn abandon_allocation_plan(&mut self) -> Result<u8> {
+        let cursor = self.allocation_cursor();
+        require!(cursor > 0, crate::errors::RebalancerError::NoAllocationPlanInProgress);
+        self.set_allocation_cursor(0, 0);
+        Ok(cursor)
+    }
+    
     pub fn validate_min_interval(interval: i64) -> Result<()> {
+        if !(3600..=86400).contains(&interval) {
+            msg!(&out_of_range_log("rebalance interval", interval, "3600-86400s"));
+        }
         require!((3600..=86400).contains(&interval), crate::errors::RebalancerError::InvalidRebalanceInterval);
         Ok(())
     }
 }
 
+impl PortfolioConfig {
+    pub const SIZE: usize = 173;
+    
+    pub fn validate(&self) -> Result<()> {
+        Portfolio::validate_rebalance_threshold(self.threshold_floor)?;
+        Portfolio::validate_rebalance_threshold(self.threshold_ceiling)?;
+        require!(
+            self.threshold_floor <= self.threshold_ceiling,
+            crate::errors::RebalancerError::InvalidRebalanceThreshold
+        );
+        require!(
+            (1..=10).contains(&self.max_extractions),
+            crate::errors::RebalancerError::TooManyStrategies
+        );
+        require!(
+            (1..=10).contains(&self.max_reward_multiplier),
+            crate::errors::RebalancerError::InvalidRewardMultiplier
+        );
+        require!(self.stale_window > 0, crate::errors::RebalancerError::InvalidRebalanceInterval);
+        require!(self.cash_buffer_bps <= 5000, crate::errors::RebalancerError::InvalidAllocationPercentage);
+        require!(
+            self.balance_floor_lamports > 0 && self.balance_floor_lamports < self.balance_cap_lamports,
+            crate::errors::RebalancerError::InvalidBalanceBand
+        );
+        require!(
+            self.yield_ceiling_bps > 0 && self.yield_ceiling_bps <= 50000,
+            crate::errors::RebalancerError::InvalidAllocationPercentage
+        );
+        require!(self.rank_hysteresis <= 25, crate::errors::RebalancerError::InvalidRebalanceThreshold);
+        require!(self.volatility_floor <= 10000, crate::errors::RebalancerError::InvalidVolatilityScore);
+        require!(
+            self.reward_risk_coupling_bps <= 20000,
+            crate::errors::RebalancerError::InvalidAllocationPercentage
+        );
+        require!(
+            self.fee_disposition == FeeDisposition::Transfer || self.fee_treasury_strategy != Pubkey::default(),
+            crate::errors::RebalancerError::InvalidTreasury
+        );
+        require!(
+            (1..=100).contains(&self.safe_harbor_volatility),
+            crate::errors::RebalancerError::InvalidSafeHarbor
+        );
+        require!(self.deprecation_rank_cutoff <= 100, crate::errors::RebalancerError::InvalidAllocationPercentage);
+        require!(self.deprecation_low_rank_cycles > 0, crate::errors::RebalancerError::InvalidRebalanceThreshold);
+        require!(self.min_score_for_allocation <= 10000, crate::errors::RebalancerError::InvalidPerformanceScore);
+        require!(self.concentration_bps <= 10000, crate::errors::RebalancerError::InvalidAllocationPercentage);
+        Ok(())
+    }
+    
+    // Parameters feeding calculate_performance_score; changing any of them makes
+    // every stored score stale
+    pub fn scoring_changed(&self, other: &PortfolioConfig) -> bool {
+        self.balance_floor_lamports != other.balance_floor_lamports
+            || self.balance_cap_lamports != other.balance_cap_lamports
+            || self.yield_ceiling_bps != other.yield_ceiling_bps
+            || self.volatility_floor != other.volatility_floor
+            || self.reward_risk_coupling_bps != other.reward_risk_coupling_bps
+            || self.balance_scaling != other.balance_scaling
+    }
+    
+    // Parameters read while applying a plan; changing them between chunks would
+    // route part of the same plan's fees one way and the rest another
+    pub fn fee_routing_changed(&self, other: &PortfolioConfig) -> bool {
+        self.fee_disposition != other.fee_disposition
+            || self.fee_treasury_strategy != other.fee_treasury_strategy
+    }
+    
+    pub fn exceeds_strategy_cap(&self, new_balance: u64) -> bool {
+        self.strategy_deposit_cap > 0 && new_balance > self.strategy_deposit_cap
+    }
+    
+    // Cross-field check against the portfolio's normal interval: the emergency path
+    // must never be slower than a regular rebalance
+    pub fn validate_against_interval(&self, min_rebalance_interval: i64) -> Result<()> {
+        require!(
+            (self.emergency_cooldown as i64) < min_rebalance_interval,
+            crate::errors::RebalancerError::InvalidRebalanceInterval
+        );
+        Ok(())
+    }
+    
+    // Portfolio-level leverage limit, stricter than the global 10x in ProtocolType::validate
+    pub fn check_reward_multiplier(&self, protocol_type: &ProtocolType) -> Result<()> {
+        if let ProtocolType::YieldFarming { reward_multiplier, .. } = protocol_type {
+            require!(
+                *reward_multiplier <= self.max_reward_multiplier,
+                crate::errors::RebalancerError::InvalidRewardMultiplier
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        PortfolioConfig {
+            threshold_floor: 10,            // 10% minimum dynamic threshold
+            threshold_ceiling: 40,          // 40% maximum dynamic threshold
+            require_funded_registration: false, // Accounting-only registration
+            smooth_scores: false,           // Rank on instantaneous scores
+            extraction_order: ExtractionOrder::WorstFirst,
+            max_extractions: 10,            // Matches extract_capital batch limit
+            allocation_strategy: AllocationStrategy::PerformanceWeighted,
+            min_update_interval: 0,         // No update rate limit
+            max_reward_multiplier: 10,      // Matches the global ProtocolType cap
+            stale_window: crate::constants::DEFAULT_STALE_WINDOW,
+            cash_buffer_bps: 0,             // Fully deploy capital
+            balance_floor_lamports: crate::constants::DEFAULT_BALANCE_FLOOR_LAMPORTS,
+            balance_cap_lamports: crate::constants::DEFAULT_BALANCE_CAP_LAMPORTS,
+            yield_ceiling_bps: crate::constants::DEFAULT_YIELD_CEILING_BPS,
+            rank_hysteresis: 0,             // Plain threshold comparison
+            warmup_window: 0,               // New strategies rank-eligible immediately
+            target_aum_cap: 0,              // Uncapped
+            aum_cap_soft: false,
+            score_check: ScoreCheckMode::Off,
+            tie_break: TieBreak::BalanceFirst,
+            extraction_cooldown: crate::constants::DEFAULT_EXTRACTION_COOLDOWN,
+            safe_harbor_strategy: None,     // Always redistribute by performance
+            safe_harbor_volatility: 80,     // 80% average volatility
+            absorb_skipped_shares: false,   // Sub-minimum shares fall to the top performer as dust
+            volatility_floor: crate::constants::DEFAULT_VOLATILITY_FLOOR,
+            fee_disposition: FeeDisposition::Transfer,
+            fee_treasury_strategy: Pubkey::default(), // Only read in Reinvest mode
+            pause_grace: 0,                 // Pauses hold until explicitly lifted
+            strategy_deposit_cap: 0,        // Uncapped
+            reward_risk_coupling_bps: 0,    // Score farms on their raw yield
+            min_allocation_lamports: 0,     // Bps and protocol minimums only
+            min_funded_after_rebalance: 0,  // Extract every selected underperformer
+            emergency_cooldown: crate::constants::DEFAULT_EMERGENCY_COOLDOWN,
+            balance_scaling: BalanceScaling::Logarithmic,
+            max_metric_staleness: 0,        // Never auto-pause on stale metrics
+            deprecation_rank_cutoff: 0,     // No low-rank tracking
+            deprecation_low_rank_cycles: 3,
+            min_score_for_allocation: 0,    // Allocate on relative performance alone
+            concentration_bps: 10000,       // Fully performance-weighted
+            stale_epoch_policy: StaleEpochPolicy::Exclude,
+            protocol_priority: [0; 3],      // No protocol preference
+            drain_paused: false,            // Paused capital stays put until resumed
+        }
+    }
+}
+
 impl Strategy {
-    pub const MAX_SIZE: usize = 8 + 200; // Account for largest protocol type
+    pub const MAX_SIZE: usize = 8 + 197 + 100; // Account for largest protocol type (YieldFarming)
     
     pub fn validate_yield_rate(rate: u64) -> Result<()> {
+        if rate > 50000 {
+            msg!(&out_of_range_log("yield rate", rate, "0-50000bps"));
+        }
         require!(rate <= 50000, crate::errors::RebalancerError::InvalidAllocationPercentage);
         Ok(())
     }
     
+    // Losses are capped at -100% (cannot lose more than the principal)
+    pub fn validate_signed_yield_rate(rate: i64) -> Result<()> {
+        require!((-10000..=50000).contains(&rate), crate::errors::RebalancerError::InvalidAllocationPercentage);
+        Ok(())
+    }
+    
+    // Signed yield when a loss has been reported; otherwise the legacy unsigned yield
+    pub fn effective_yield_rate(&self) -> i64 {
+        if self.yield_rate_signed < 0 {
+            self.yield_rate_signed
+        } else {
+            self.yield_rate as i64
+        }
+    }
+    
+    // REWARD ACCRUAL
+    // Reinvesting strategies get the reward back to compound into current_balance
+    // (the caller credits it like a deposit); others hold it in pending_rewards and
+    // get 0. Only reward-bearing protocols accrue.
+    pub fn accrue_rewards(&mut self, reward_amount: u64) -> Result<u64> {
+        require!(
+            matches!(self.protocol_type.kind(), ProtocolKind::YieldFarming | ProtocolKind::LiquidStaking),
+            crate::errors::RebalancerError::InvalidProtocolType
+        );
+        require!(reward_amount > 0, crate::errors::RebalancerError::InsufficientBalance);
+        
+        if self.reinvest_rewards {
+            return Ok(reward_amount);
+        }
+        self.pending_rewards = self.pending_rewards
+            .checked_add(reward_amount)
+            .ok_or(crate::errors::RebalancerError::BalanceOverflow)?;
+        Ok(0)
+    }
+    
+    // Still inside the post-creation warmup window (0 = no warmup)
+    pub fn in_warmup(&self, now: i64, warmup_window: u32) -> bool {
+        warmup_window > 0 && now < self.creation_time.saturating_add(warmup_window as i64)
+    }
+    
+    // Capital may be pulled out: active strategies always, paused ones only when the
+    // portfolio drains paused strategies. Funding still requires Active.
+    pub fn extraction_eligible(&self, drain_paused: bool) -> bool {
+        self.status == StrategyStatus::Active
+            || (drain_paused && self.status == StrategyStatus::Paused)
+    }
+    
+    // Score predates the portfolio's current scoring formula
+    pub fn needs_rescore(&self, portfolio_scoring_epoch: u32) -> bool {
+        self.scoring_epoch != portfolio_scoring_epoch
+    }
+    
+    // CONSECUTIVE LOW RANKS
+    // Called after each ranking: counts rankings below the cutoff in a row, reset by
+    // any ranking at or above it (cutoff 0 = tracking off)
+    pub fn track_low_rank(&mut self, rank_cutoff: u8) {
+        let count = if rank_cutoff > 0 && self.percentile_rank < rank_cutoff {
+            self.consecutive_low_ranks().saturating_add(1)
+        } else {
+            0
+        };
+        self.set_consecutive_low_ranks(count);
+    }
+    
+    // METRIC STALENESS
+    // Measured from the last performance update; last_updated is also stamped by every
+    // ranking, so a frequently ranked strategy would otherwise never go stale
+    pub fn metrics_stale(&self, now: i64, max_metric_staleness: u32) -> bool {
+        max_metric_staleness > 0 && now.saturating_sub(self.last_performance_update) > max_metric_staleness as i64
+    }
+    
+    // EXTRACTION COOLDOWN
+    pub fn start_extraction_cooldown(&mut self, now: i64, cooldown: u32) {
+        self.extraction_cooldown_until = now.saturating_add(cooldown as i64);
+    }
+    
+    // Age of the latest performance update (rankings do not refresh it); clamped to
+    // zero if the clock is behind it
+    pub fn seconds_since_update(&self, now: i64) -> i64 {
+        now.saturating_sub(self.last_performance_update).max(0)
+    }
+    
+    // HIGH-WATER-MARK PERFORMANCE FEE
+    // Only balance above the previous peak is a chargeable gain; recovering
+    // earlier losses is not
+    pub fn performance_fee_due(&self, fee_bps: u16) -> Result<u64> {
+        let gain = self.current_balance.saturating_sub(self.high_water_mark);
+        let fee = (gain as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(crate::errors::RebalancerError::BalanceOverflow)?
+            / 10000;
+        Ok(fee as u64)
+    }
+    
+    // Charges the fee against current_balance and raises the mark to the new peak.
+    // Returns the fee charged.
+    pub fn charge_performance_fee(&mut self, fee_bps: u16) -> Result<u64> {
+        let fee = self.performance_fee_due(fee_bps)?;
+        
+        self.current_balance = self.current_balance
+            .checked_sub(fee)
+            .ok_or(crate::errors::RebalancerError::InsufficientBalance)?;
+        self.total_withdrawals = self.total_withdrawals
+            .checked_add(fee)
+            .ok_or(crate::errors::RebalancerError::BalanceOverflow)?;
+        self.high_water_mark = self.high_water_mark.max(self.current_balance);
+        
+        Ok(fee)
+    }
+    
+    // QUOTE NORMALIZATION
+    // Converts a balance held in quote_mint units to lamports using an oracle price
+    // (lamports per whole quote token). Native-SOL strategies pass through unchanged.
+    pub fn normalize_balance(&self, quote_balance: u64, quote_price_lamports: u64) -> Result<u64> {
+        if self.quote_mint == Pubkey::default() {
+            return Ok(quote_balance);
+        }
+        
+        require!(quote_price_lamports > 0, crate::errors::RebalancerError::InvalidQuotePrice);
+        let scale = 10u128
+            .checked_pow(self.quote_decimals as u32)
+            .ok_or(crate::errors::RebalancerError::MathOverflow)?;
+        let lamports = (quote_balance as u128)
+            .checked_mul(quote_price_lamports as u128)
+            .ok_or(crate::errors::RebalancerError::MathOverflow)?
+            / scale;
+        
+        u64::try_from(lamports).map_err(|_| crate::errors::RebalancerError::MathOverflow.into())
+    }
+    
     pub fn validate_balance_update(new_balance: u64) -> Result<()> {
         require!(new_balance < u64::MAX / 1000, crate::errors::RebalancerError::MathOverflow);
         Ok(())
     }
     
     pub fn validate_volatility_score(score: u32) -> Result<()> {
+        if score > 10000 {
+            msg!(&out_of_range_log("volatility score", score, "0-10000"));
+        }
         require!(score <= 10000, crate::errors::RebalancerError::InvalidAllocationPercentage);
         Ok(())
     }
+    
+    pub fn ranking_score(&self, smooth_scores: bool) -> u64 {
+        if smooth_scores {
+            self.smoothed_score
+        } else {
+            self.performance_score
+        }
+    }
+    
+    // Any explicit transition supersedes an automatic stale-metrics pause
+    pub fn transition_status(&mut self, next: StrategyStatus) -> Result<()> {
+        require!(
+            self.status.can_transition_to(next),
+            crate::errors::RebalancerError::InvalidStatusTransition
+        );
+        self.status = next;
+        self.set_auto_paused(false);
+        Ok(())
+    }
 }
 
 impl ProtocolType {
@@ -162,6 +795,32 @@ impl ProtocolType {
         }
     }
     
+    // Borsh variant tag of this protocol type
+    pub fn discriminant(&self) -> u8 {
+        match self {
+            ProtocolType::StableLending { .. } => 0,
+            ProtocolType::YieldFarming { .. } => 1,
+            ProtocolType::LiquidStaking { .. } => 2,
+        }
+    }
+    
+    pub fn kind(&self) -> ProtocolKind {
+        match self {
+            ProtocolType::StableLending { .. } => ProtocolKind::StableLending,
+            ProtocolType::YieldFarming { .. } => ProtocolKind::YieldFarming,
+            ProtocolType::LiquidStaking { .. } => ProtocolKind::LiquidStaking,
+        }
+    }
+    
+    pub fn from_discriminant(discriminant: u8) -> Option<ProtocolKind> {
+        match discriminant {
+            0 => Some(ProtocolKind::StableLending),
+            1 => Some(ProtocolKind::YieldFarming),
+            2 => Some(ProtocolKind::LiquidStaking),
+            _ => None,
+        }
+    }
+    
     pub fn get_protocol_name(&self) -> &'static str {
         match self {
             ProtocolType::StableLending { .. } => "Stable Lending",
@@ -170,6 +829,14 @@ impl ProtocolType {
         }
     }
     
+    pub fn default_volatility(&self) -> u32 {
+        match self {
+            ProtocolType::StableLending { .. } => crate::constants::DEFAULT_VOLATILITY_STABLE_LENDING,
+            ProtocolType::YieldFarming { .. } => crate::constants::DEFAULT_VOLATILITY_YIELD_FARMING,
+            ProtocolType::LiquidStaking { .. } => crate::constants::DEFAULT_VOLATILITY_LIQUID_STAKING,
+        }
+    }
+    
     pub fn get_expected_tokens(&self) -> Vec<Pubkey> {
         match self {
             ProtocolType::StableLending { reserve_address, .. } => {
@@ -184,29 +851,366 @@ impl ProtocolType {
         }
     }
     
-    pub fn validate_balance_constraints(&self, balance: u64) -> Result<()> {
+    pub fn min_balance(&self) -> u64 {
         match self {
-            ProtocolType::StableLending { .. } => {
-                // Minimum 0.1 SOL for lending protocols
-                require!(balance >= 100_000_000, crate::errors::RebalancerError::InsufficientBalance);
-            },
-            ProtocolType::YieldFarming { .. } => {
-                // Minimum 0.5 SOL for LP positions (gas + slippage)
-                require!(balance >= 500_000_000, crate::errors::RebalancerError::InsufficientBalance);
-            },
-            ProtocolType::LiquidStaking { .. } => {
-                // Minimum 1 SOL for staking (epoch requirements)
-                require!(balance >= 1_000_000_000, crate::errors::RebalancerError::InsufficientBalance);
-            },
+            ProtocolType::StableLending { .. } => 100_000_000,   // 0.1 SOL for lending protocols
+            ProtocolType::YieldFarming { .. } => 500_000_000,    // 0.5 SOL for LP positions (gas + slippage)
+            ProtocolType::LiquidStaking { .. } => 1_000_000_000, // 1 SOL for staking (epoch requirements)
         }
+    }
+    
+    pub fn validate_balance_constraints(&self, balance: u64) -> Result<()> {
+        require!(balance >= self.min_balance(), crate::errors::RebalancerError::InsufficientBalance);
         Ok(())
     }
 }
 
+impl StrategyStatus {
+    // Deprecated is terminal; Active and Paused can move between each other or be deprecated
+    pub fn can_transition_to(&self, next: StrategyStatus) -> bool {
+        matches!(
+            (self, next),
+            (StrategyStatus::Active, StrategyStatus::Paused)
+                | (StrategyStatus::Active, StrategyStatus::Deprecated)
+                | (StrategyStatus::Paused, StrategyStatus::Active)
+                | (StrategyStatus::Paused, StrategyStatus::Deprecated)
+        )
+    }
+}
+
 impl CapitalPosition {
     pub const MAX_SIZE: usize = 8 + 145;
 }
 
+// Diagnostic logged right before a validator rejects a value, so clients see
+// what was received alongside the typed error code
+pub fn out_of_range_log(field: &str, received: impl std::fmt::Display, allowed: &str) -> String {
+    format!("Invalid {}: received {}, allowed {}", field, received, allowed)
+}
+
+// VOLATILITY UNITS
+// Volatility is carried in basis points of 100% (0-10000, the on-chain and
+// instruction-argument unit); build it through from_bps / from_percent so no
+// caller has to guess which scale a raw number is in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VolatilityScore(u32);
+
+impl VolatilityScore {
+    pub const MAX_BPS: u32 = 10000;
+    
+    pub fn from_bps(bps: u32) -> Result<Self> {
+        Strategy::validate_volatility_score(bps)?;
+        Ok(Self(bps))
+    }
+    
+    pub fn from_percent(percent: u32) -> Result<Self> {
+        if percent > 100 {
+            msg!(&out_of_range_log("volatility percent", percent, "0-100"));
+        }
+        require!(percent <= 100, crate::errors::RebalancerError::InvalidVolatilityScore);
+        Ok(Self(percent * 100))
+    }
+    
+    // For values already stored on a strategy: out-of-range scores degrade to the maximum
+    pub fn saturating_from_bps(bps: u32) -> Self {
+        Self(bps.min(Self::MAX_BPS))
+    }
+    
+    pub fn bps(self) -> u32 {
+        self.0
+    }
+    
+    // Whole percent, truncated (0-100)
+    pub fn percent(self) -> u32 {
+        self.0 / 100
+    }
+    
+    // Inverse on the same scale: 0 volatility -> 10000
+    pub fn inverse_bps(self) -> u32 {
+        Self::MAX_BPS - self.0
+    }
+}
+
+// RENT-SAFE RESIDUAL
+// Lamports every capital-moving path leaves behind for an account of data_len bytes:
+// rent-exempt minimum plus a small buffer
+pub fn rent_keep_lamports(data_len: usize) -> u64 {
+    Rent::default()
+        .minimum_balance(data_len)
+        .saturating_add(crate::constants::RENT_KEEP_BUFFER_LAMPORTS)
+}
+
+// Portion of a balance that may be moved without touching the rent residual of the
+// account being drained (data_len = that account's actual data length)
+pub fn extractable_balance(balance: u64, data_len: usize) -> u64 {
+    balance.saturating_sub(rent_keep_lamports(data_len))
+}
+
+impl CreationRateLimit {
+    pub const MAX_SIZE: usize = 8 + 42;
+    
+    // Counts a creation in the current window, opening a new window once the old one expires
+    pub fn record_creation(&mut self, current_time: i64) -> Result<()> {
+        if current_time >= self.window_start.saturating_add(crate::constants::PORTFOLIO_CREATION_WINDOW) {
+            self.window_start = current_time;
+            self.count = 0;
+        }
+        
+        require!(
+            self.count < crate::constants::MAX_PORTFOLIOS_PER_WINDOW,
+            crate::errors::RebalancerError::CreationRateLimited
+        );
+        self.count += 1;
+        
+        Ok(())
+    }
+}
+
+impl TreasuryWhitelist {
+    pub const MAX_SIZE: usize = 8 + 162;
+    
+    pub fn authorized(&self) -> &[Pubkey] {
+        &self.treasuries[..self.count as usize]
+    }
+    
+    pub fn is_authorized(&self, treasury: &Pubkey) -> bool {
+        self.authorized().contains(treasury)
+    }
+    
+    pub fn add(&mut self, treasury: Pubkey) -> Result<()> {
+        require!(treasury != Pubkey::default(), crate::errors::RebalancerError::InvalidTreasury);
+        require!(!self.is_authorized(&treasury), crate::errors::RebalancerError::InvalidTreasury);
+        require!(
+            (self.count as usize) < crate::constants::MAX_AUTHORIZED_TREASURIES,
+            crate::errors::RebalancerError::TreasuryWhitelistFull
+        );
+        
+        self.treasuries[self.count as usize] = treasury;
+        self.count += 1;
+        Ok(())
+    }
+    
+    // Swap-removes to keep occupied slots contiguous
+    pub fn remove(&mut self, treasury: &Pubkey) -> Result<()> {
+        let index = self
+            .authorized()
+            .iter()
+            .position(|t| t == treasury)
+            .ok_or(crate::errors::RebalancerError::InvalidTreasury)?;
+        
+        let last = self.count as usize - 1;
+        self.treasuries[index] = self.treasuries[last];
+        self.treasuries[last] = Pubkey::default();
+        self.count -= 1;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Debug)]
+pub struct StrategyRegistry {
+    pub portfolio: Pubkey,                  // 32 bytes - Portfolio whose strategies are listed
+    pub page: u32,                          // 4 bytes - Page index (seed component)
+    pub count: u8,                          // 1 byte - Occupied slots in strategy_ids
+    pub strategy_ids: [Pubkey; 32],         // 1024 bytes - Registered strategy ids
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 1062 bytes
+
+impl StrategyRegistry {
+    pub const MAX_SIZE: usize = 8 + 1062;
+    
+    pub fn strategy_ids(&self) -> &[Pubkey] {
+        &self.strategy_ids[..self.count as usize]
+    }
+    
+    pub fn is_full(&self) -> bool {
+        self.count as usize >= crate::constants::REGISTRY_PAGE_CAPACITY
+    }
+    
+    pub fn append(&mut self, strategy_id: Pubkey) -> Result<()> {
+        require!(!self.is_full(), crate::errors::RebalancerError::RegistryPageFull);
+        require!(!self.strategy_ids().contains(&strategy_id), crate::errors::RebalancerError::DuplicateStrategy);
+        
+        self.strategy_ids[self.count as usize] = strategy_id;
+        self.count += 1;
+        Ok(())
+    }
+    
+    // Swap-removes to keep occupied slots contiguous
+    pub fn remove(&mut self, strategy_id: &Pubkey) -> Result<()> {
+        let index = self
+            .strategy_ids()
+            .iter()
+            .position(|id| id == strategy_id)
+            .ok_or(crate::errors::RebalancerError::StrategyNotFound)?;
+        
+        let last = self.count as usize - 1;
+        self.strategy_ids[index] = self.strategy_ids[last];
+        self.strategy_ids[last] = Pubkey::default();
+        self.count -= 1;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Debug)]
+pub struct DepositQueue {
+    pub portfolio: Pubkey,                  // 32 bytes - Portfolio owning the strategy
+    pub strategy: Pubkey,                   // 32 bytes - Strategy account the deposits are waiting on
+    pub count: u8,                          // 1 byte - Occupied slots in amounts
+    pub amounts: [u64; 16],                 // 128 bytes - Pending deposit amounts, oldest first
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 194 bytes
+
+impl DepositQueue {
+    pub const MAX_SIZE: usize = 8 + 194;
+    
+    pub fn pending(&self) -> &[u64] {
+        &self.amounts[..self.count as usize]
+    }
+    
+    pub fn is_full(&self) -> bool {
+        self.count as usize >= crate::constants::MAX_QUEUED_DEPOSITS
+    }
+    
+    // An intent larger than the cap could never be released and would block every
+    // later one, so it is rejected outright (cap 0 = uncapped)
+    pub fn enqueue(&mut self, amount: u64, cap: u64) -> Result<()> {
+        require!(cap == 0 || amount <= cap, crate::errors::RebalancerError::StrategyAtCapacity);
+        require!(!self.is_full(), crate::errors::RebalancerError::DepositQueueFull);
+        
+        self.amounts[self.count as usize] = amount;
+        self.count += 1;
+        Ok(())
+    }
+    
+    // Drops the intent at `index`, keeping the rest in order; returns its amount
+    pub fn remove(&mut self, index: u8) -> Result<u64> {
+        require!(index < self.count, crate::errors::RebalancerError::QueuedDepositNotFound);
+        
+        let (index, count) = (index as usize, self.count as usize);
+        let amount = self.amounts[index];
+        self.amounts.copy_within(index + 1..count, index);
+        self.amounts[count - 1] = 0;
+        self.count -= 1;
+        Ok(amount)
+    }
+    
+    // Pops pending deposits in FIFO order while they fit under the cap and
+    // returns their total; stops at the first one that does not fit
+    pub fn take_fitting(&mut self, current_balance: u64, cap: u64) -> u64 {
+        let mut balance = current_balance;
+        let mut taken = 0;
+        
+        for &amount in self.pending() {
+            match balance.checked_add(amount) {
+                Some(next) if cap == 0 || next <= cap => balance = next,
+                _ => break,
+            }
+            taken += 1;
+        }
+        
+        let count = self.count as usize;
+        self.amounts.copy_within(taken..count, 0);
+        self.amounts[count - taken..count].fill(0);
+        self.count -= taken as u8;
+        balance - current_balance
+    }
+}
+
+// PACKED STRATEGY SUMMARIES
+// Ranking inputs for many strategies in one account, so a ranking pass reads a
+// single account instead of one Strategy account per strategy
+#[account]
+#[derive(Debug)]
+pub struct StrategySummaryPack {
+    pub portfolio: Pubkey,                  // 32 bytes - Portfolio whose strategies are summarized
+    pub count: u16,                         // 2 bytes - Occupied slots in summaries
+    pub summaries: [PackedStrategySummary; 128], // 6784 bytes - Active strategy summaries
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 6819 bytes
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PackedStrategySummary {
+    pub strategy_id: Pubkey,                // 32 bytes - Strategy this summary mirrors
+    pub performance_score: u64,             // 8 bytes - Ranking score at last sync
+    pub current_balance: u64,               // 8 bytes - Balance at last sync (lamports)
+    pub volatility_score: u32,              // 4 bytes - Volatility at last sync (0-10000)
+    pub percentile_rank: u8,                // 1 byte - Strategy's batch-ranking percentile at last sync
+}
+// Total: 53 bytes
+
+impl StrategySummaryPack {
+    pub const MAX_SIZE: usize = 8 + 6819;
+    
+    pub fn summaries(&self) -> &[PackedStrategySummary] {
+        &self.summaries[..self.count as usize]
+    }
+    
+    pub fn is_full(&self) -> bool {
+        self.count as usize >= crate::constants::MAX_PACKED_SUMMARIES
+    }
+    
+    // Refreshes the strategy's summary in place, or appends it if it is not packed yet
+    pub fn upsert(&mut self, summary: PackedStrategySummary) -> Result<()> {
+        if let Some(existing) = self.summaries[..self.count as usize]
+            .iter_mut()
+            .find(|s| s.strategy_id == summary.strategy_id)
+        {
+            *existing = summary;
+            return Ok(());
+        }
+        
+        require!(!self.is_full(), crate::errors::RebalancerError::SummaryPackFull);
+        self.summaries[self.count as usize] = summary;
+        self.count += 1;
+        Ok(())
+    }
+    
+    // Swap-removes to keep occupied slots contiguous; false when the strategy was not packed
+    pub fn remove(&mut self, strategy_id: &Pubkey) -> bool {
+        let Some(index) = self.summaries().iter().position(|s| &s.strategy_id == strategy_id) else {
+            return false;
+        };
+        
+        let last = self.count as usize - 1;
+        self.summaries[index] = self.summaries[last];
+        self.summaries[last] = PackedStrategySummary::default();
+        self.count -= 1;
+        true
+    }
+}
+
+#[account]
+#[derive(Debug)]
+pub struct GuardianApproval {
+    pub portfolio: Pubkey,                  // 32 bytes - Portfolio being frozen/unfrozen
+    pub freeze: bool,                       // 1 byte - Action voted on (true = freeze, false = unfreeze)
+    pub approvals: u8,                      // 1 byte - Bitmap of approving guardian slots
+    pub guardian_set_version: u8,           // 1 byte - Guardian set the approvals were cast under
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 36 bytes
+
+impl GuardianApproval {
+    pub const MAX_SIZE: usize = 8 + 36;
+    
+    // Records the guardian's approval (idempotent) and reports whether the threshold
+    // is met. Approvals cast under an older guardian set are discarded first.
+    pub fn approve(&mut self, guardian_index: usize, threshold: u8, guardian_set_version: u8) -> bool {
+        if self.guardian_set_version != guardian_set_version {
+            self.approvals = 0;
+            self.guardian_set_version = guardian_set_version;
+        }
+        
+        self.approvals |= 1 << guardian_index;
+        self.approvals.count_ones() >= threshold as u32
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct CapitalAllocation {
     pub strategy_id: Pubkey,
@@ -220,4 +1224,12 @@ pub enum AllocationType {
     RiskDiversification,
     ManagerIncentive,
     PlatformFee,
-}
\ No newline at end of file
+    CashReserve,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PriceUpdate {
+    pub strategy_id: Pubkey,
+    pub current_price_a: u64,               // Current price token A (6 decimals)
+    pub current_price_b: u64,               // Current price token B (6 decimals)
+}
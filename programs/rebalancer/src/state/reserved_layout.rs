@@ -0,0 +1,145 @@
+use std::ops::Range;
+use super::{Portfolio, Strategy};
+
+// RESERVED-BYTE LAYOUT
+// Every field carved out of a `reserved` buffer is listed here with its byte range,
+// and these accessors are the only code allowed to read or write `reserved`.
+// New entries must start at or after the end of the previous one.
+
+// Portfolio.reserved ([u8; 23])
+pub const PORTFOLIO_LAST_DYNAMIC_THRESHOLD: Range<usize> = 0..1;   // u8 - threshold used by the last batch ranking
+pub const PORTFOLIO_LAST_RANKED_AT: Range<usize> = 1..9;           // i64 LE - timestamp of the last batch ranking
+pub const PORTFOLIO_PAUSE_EXPIRES_AT: Range<usize> = 9..17;        // i64 LE - emergency pause auto-expiry (0 = indefinite)
+pub const PORTFOLIO_RANKING_FLAGS: Range<usize> = 17..18;          // u8 bitflags - RANKING_FLAG_*
+pub const PORTFOLIO_ALLOCATION_CURSOR: Range<usize> = 18..19;      // u8 - next allocation of a partially applied plan (0 = none)
+pub const PORTFOLIO_ALLOCATION_PLAN_TAG: Range<usize> = 19..23;    // u32 LE - hash prefix of the partially applied plan
+
+// PORTFOLIO_RANKING_FLAGS bits (bit 0 was the original bool, so old accounts read unchanged)
+pub const RANKING_FLAG_CYCLE_OPEN: u8 = 1 << 0;                   // cycle started, batch ranking not yet run
+pub const RANKING_FLAG_IN_PROGRESS: u8 = 1 << 1;                  // ranking lock held (see Portfolio::with_ranking_lock)
+
+// Strategy.reserved ([u8; 4])
+pub const STRATEGY_RANK_STALE: Range<usize> = 0..1;                // bool - balance changed since the last ranking
+pub const STRATEGY_CONSECUTIVE_LOW_RANKS: Range<usize> = 1..2;     // u8 - rankings below deprecation_rank_cutoff in a row
+pub const STRATEGY_DEPOSITS_QUEUED: Range<usize> = 2..3;           // bool - intents are pending in the deposit queue
+pub const STRATEGY_AUTO_PAUSED: Range<usize> = 3..4;               // bool - paused by ranking for stale metrics, not by the manager
+
+// Compile-time overlap and bounds checks
+const _: () = assert!(PORTFOLIO_LAST_DYNAMIC_THRESHOLD.end <= PORTFOLIO_LAST_RANKED_AT.start);
+const _: () = assert!(PORTFOLIO_LAST_RANKED_AT.end <= PORTFOLIO_PAUSE_EXPIRES_AT.start);
+const _: () = assert!(PORTFOLIO_PAUSE_EXPIRES_AT.end <= PORTFOLIO_RANKING_FLAGS.start);
+const _: () = assert!(PORTFOLIO_RANKING_FLAGS.end <= PORTFOLIO_ALLOCATION_CURSOR.start);
+const _: () = assert!(PORTFOLIO_ALLOCATION_CURSOR.end <= PORTFOLIO_ALLOCATION_PLAN_TAG.start);
+const _: () = assert!(PORTFOLIO_ALLOCATION_PLAN_TAG.end <= 23);
+const _: () = assert!(STRATEGY_RANK_STALE.end <= STRATEGY_CONSECUTIVE_LOW_RANKS.start);
+const _: () = assert!(STRATEGY_CONSECUTIVE_LOW_RANKS.end <= STRATEGY_DEPOSITS_QUEUED.start);
+const _: () = assert!(STRATEGY_DEPOSITS_QUEUED.end <= STRATEGY_AUTO_PAUSED.start);
+const _: () = assert!(STRATEGY_AUTO_PAUSED.end <= 4);
+
+impl Portfolio {
+    pub fn last_dynamic_threshold(&self) -> u8 {
+        self.reserved[PORTFOLIO_LAST_DYNAMIC_THRESHOLD.start]
+    }
+
+    pub fn set_last_dynamic_threshold(&mut self, threshold: u8) {
+        self.reserved[PORTFOLIO_LAST_DYNAMIC_THRESHOLD.start] = threshold;
+    }
+
+    pub fn last_ranked_at(&self) -> i64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.reserved[PORTFOLIO_LAST_RANKED_AT]);
+        i64::from_le_bytes(bytes)
+    }
+
+    pub fn set_last_ranked_at(&mut self, timestamp: i64) {
+        self.reserved[PORTFOLIO_LAST_RANKED_AT].copy_from_slice(&timestamp.to_le_bytes());
+    }
+
+    pub fn pause_expires_at(&self) -> i64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.reserved[PORTFOLIO_PAUSE_EXPIRES_AT]);
+        i64::from_le_bytes(bytes)
+    }
+
+    pub fn set_pause_expires_at(&mut self, timestamp: i64) {
+        self.reserved[PORTFOLIO_PAUSE_EXPIRES_AT].copy_from_slice(&timestamp.to_le_bytes());
+    }
+
+    fn ranking_flag(&self, flag: u8) -> bool {
+        self.reserved[PORTFOLIO_RANKING_FLAGS.start] & flag != 0
+    }
+
+    fn set_ranking_flag(&mut self, flag: u8, set: bool) {
+        let flags = &mut self.reserved[PORTFOLIO_RANKING_FLAGS.start];
+        if set {
+            *flags |= flag;
+        } else {
+            *flags &= !flag;
+        }
+    }
+
+    pub fn ranking_cycle_open(&self) -> bool {
+        self.ranking_flag(RANKING_FLAG_CYCLE_OPEN)
+    }
+
+    pub fn set_ranking_cycle_open(&mut self, open: bool) {
+        self.set_ranking_flag(RANKING_FLAG_CYCLE_OPEN, open);
+    }
+
+    pub fn ranking_in_progress(&self) -> bool {
+        self.ranking_flag(RANKING_FLAG_IN_PROGRESS)
+    }
+
+    pub fn set_ranking_in_progress(&mut self, in_progress: bool) {
+        self.set_ranking_flag(RANKING_FLAG_IN_PROGRESS, in_progress);
+    }
+
+    pub fn allocation_cursor(&self) -> u8 {
+        self.reserved[PORTFOLIO_ALLOCATION_CURSOR.start]
+    }
+
+    pub fn allocation_plan_tag(&self) -> u32 {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&self.reserved[PORTFOLIO_ALLOCATION_PLAN_TAG]);
+        u32::from_le_bytes(bytes)
+    }
+
+    pub fn set_allocation_cursor(&mut self, cursor: u8, plan_tag: u32) {
+        self.reserved[PORTFOLIO_ALLOCATION_CURSOR.start] = cursor;
+        self.reserved[PORTFOLIO_ALLOCATION_PLAN_TAG].copy_from_slice(&plan_tag.to_le_bytes());
+    }
+}
+
+impl Strategy {
+    pub fn rank_stale(&self) -> bool {
+        self.reserved[STRATEGY_RANK_STALE.start] != 0
+    }
+
+    pub fn set_rank_stale(&mut self, stale: bool) {
+        self.reserved[STRATEGY_RANK_STALE.start] = stale as u8;
+    }
+
+    pub fn consecutive_low_ranks(&self) -> u8 {
+        self.reserved[STRATEGY_CONSECUTIVE_LOW_RANKS.start]
+    }
+
+    pub fn set_consecutive_low_ranks(&mut self, count: u8) {
+        self.reserved[STRATEGY_CONSECUTIVE_LOW_RANKS.start] = count;
+    }
+
+    pub fn deposits_queued(&self) -> bool {
+        self.reserved[STRATEGY_DEPOSITS_QUEUED.start] != 0
+    }
+
+    pub fn set_deposits_queued(&mut self, queued: bool) {
+        self.reserved[STRATEGY_DEPOSITS_QUEUED.start] = queued as u8;
+    }
+
+    pub fn auto_paused(&self) -> bool {
+        self.reserved[STRATEGY_AUTO_PAUSED.start] != 0
+    }
+
+    pub fn set_auto_paused(&mut self, auto_paused: bool) {
+        self.reserved[STRATEGY_AUTO_PAUSED.start] = auto_paused as u8;
+    }
+}
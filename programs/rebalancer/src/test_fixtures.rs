@@ -0,0 +1,81 @@
+// SHARED TEST FIXTURES
+// Baseline accounts for unit tests. Tests override only the fields they care about
+// with struct-update syntax (`Strategy { pinned: true, ..test_fixtures::strategy() }`),
+// so a new account field is added here once instead of in every test.
+use anchor_lang::prelude::*;
+use crate::instructions::update_performance::apply_performance_update;
+use crate::state::*;
+
+pub fn portfolio() -> Portfolio {
+    let manager = Pubkey::new_unique();
+    Portfolio {
+        manager,
+        rebalance_threshold: 25,
+        total_strategies: 3,
+        total_capital_moved: 0,
+        last_rebalance: 0,
+        min_rebalance_interval: 3600,
+        portfolio_creation: 0,
+        emergency_pause: false,
+        performance_fee_bps: 200,
+        ranking_cycle_count: 0,
+        guardians: [Pubkey::default(); 5],
+        guardian_threshold: 0,
+        guardian_set_version: 0,
+        config: PortfolioConfig::default(),
+        bump: 255,
+        total_aum: 0,
+        base_currency: crate::constants::DEFAULT_BASE_CURRENCY,
+        scoring_epoch: 0,
+        seed_manager: manager,
+        pending_manager: Pubkey::default(),
+        reserved: [0u8; 23],
+    }
+}
+
+// Active, unscored lending strategy holding its 1 SOL deposit
+pub fn strategy() -> Strategy {
+    Strategy {
+        strategy_id: Pubkey::new_unique(),
+        protocol_type: ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 8000,
+            reserve_address: Pubkey::new_unique(),
+        },
+        current_balance: 0,
+        yield_rate: 0,
+        volatility_score: 0,
+        performance_score: 0,
+        smoothed_score: 0,
+        percentile_rank: 50,
+        last_updated: 0,
+        status: StrategyStatus::Active,
+        total_deposits: 1_000_000_000,
+        total_withdrawals: 0,
+        creation_time: 0,
+        target_weight_bps: 0,
+        quote_mint: Pubkey::default(),
+        quote_decimals: 9,
+        bump: 255,
+        yield_rate_signed: 0,
+        pinned: false,
+        reinvest_rewards: false,
+        pending_rewards: 0,
+        awaiting_recovery: false,
+        high_water_mark: 0,
+        extraction_cooldown_until: 0,
+        scoring_epoch: 0,
+        last_performance_update: 0,
+        reserved: [0; 4],
+    }
+}
+
+// Strategy scored through a real performance update (3000 volatility, 1 SOL) at `updated_at`
+pub fn scored_strategy(config: &PortfolioConfig, yield_rate: i64, updated_at: i64) -> Strategy {
+    let mut strategy = Strategy {
+        high_water_mark: 1_000_000_000,
+        ..strategy()
+    };
+    apply_performance_update(&mut strategy, config, yield_rate, 3000, 1_000_000_000, false, updated_at).unwrap();
+    strategy
+}
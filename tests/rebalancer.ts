@@ -8,6 +8,7 @@ describe("rebalancer", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.Rebalancer as Program<Rebalancer>;
+  const baseCurrency = Array.from(Buffer.from("SOL".padEnd(8, "\0")));
   const manager = anchor.web3.Keypair.generate();
 
   before(async () => {
@@ -26,7 +27,8 @@ describe("rebalancer", () => {
       .initializePortfolio(
         manager.publicKey,
         25, // 25% rebalance threshold
-        new anchor.BN(3600) // 1 hour minimum interval
+        new anchor.BN(3600), // 1 hour minimum interval
+        baseCurrency
       )
       .accountsPartial({
         portfolio: portfolioPda,
@@ -244,7 +246,8 @@ describe("rebalancer performance scoring", () => {
       .initializePortfolio(
         performanceManager.publicKey,
         25, // 25% rebalance threshold
-        new anchor.BN(3600) // 1 hour minimum interval
+        new anchor.BN(3600), // 1 hour minimum interval
+        baseCurrency
       )
       .accountsPartial({
         portfolio: portfolioPda,
@@ -441,7 +444,8 @@ describe("rebalancer performance scoring", () => {
       .initializePortfolio(
         quickManagerKey.publicKey,
         25, // 25% rebalance threshold
-        new anchor.BN(3600) // 1 hour minimum interval (minimum allowed)
+        new anchor.BN(3600), // 1 hour minimum interval (minimum allowed)
+        baseCurrency
       )
       .accountsPartial({
         portfolio: quickPortfolioPda,
@@ -717,7 +721,8 @@ describe("rebalancer complete workflow", () => {
       .initializePortfolio(
         manager.publicKey,
         25, // 25% rebalance threshold
-        new anchor.BN(3600) // 1 hour minimum interval (minimum allowed)
+        new anchor.BN(3600), // 1 hour minimum interval (minimum allowed)
+        baseCurrency
       )
       .accountsPartial({
         portfolio: portfolioPda,
@@ -1094,7 +1099,8 @@ describe("rebalancer complete workflow", () => {
         .initializePortfolio(
           dynamicManager.publicKey,
           25, // Placeholder threshold - dynamic system will override this
-          new anchor.BN(3600) // 1 hour minimum interval
+          new anchor.BN(3600), // 1 hour minimum interval
+          baseCurrency
         )
         .accountsPartial({
           portfolio: dynamicPortfolioPda,
@@ -1352,3 +1358,119 @@ describe("rebalancer complete workflow", () => {
     });
   });
 });
+
+describe("rebalancer funded registration", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Rebalancer as Program<Rebalancer>;
+  const fundedManager = anchor.web3.Keypair.generate();
+
+  let portfolioPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.requestAirdrop(fundedManager.publicKey, 5 * anchor.web3.LAMPORTS_PER_SOL);
+    await new Promise(resolve => setTimeout(resolve, 1000)); // Wait for airdrop
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), fundedManager.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(fundedManager.publicKey, 25, new anchor.BN(3600), baseCurrency)
+      .accountsPartial({
+        portfolio: portfolioPda,
+        payer: provider.wallet.publicKey,
+        manager: fundedManager.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Enable funded registration mode
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    await program.methods
+      .updatePortfolioConfig({ ...portfolio.config, requireFundedRegistration: true })
+      .accountsPartial({
+        portfolio: portfolioPda,
+        manager: fundedManager.publicKey,
+      })
+      .signers([fundedManager])
+      .rpc();
+  });
+
+  it("Transfers initial balance into the strategy vault", async () => {
+    const strategyId = anchor.web3.Keypair.generate().publicKey;
+    const [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+    const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), strategyPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 7500,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new anchor.BN(1000000000) // 1 SOL
+      )
+      .accountsPartial({
+        portfolio: portfolioPda,
+        strategy: strategyPda,
+        vault: vaultPda,
+        manager: fundedManager.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([fundedManager])
+      .rpc();
+
+    const vaultBalance = await provider.connection.getBalance(vaultPda);
+    expect(vaultBalance).to.equal(1000000000);
+
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.currentBalance.toString()).to.equal("1000000000");
+  });
+
+  it("Rejects funded registration without a vault", async () => {
+    const strategyId = anchor.web3.Keypair.generate().publicKey;
+    const [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .registerStrategy(
+          strategyId,
+          {
+            stableLending: {
+              poolId: anchor.web3.Keypair.generate().publicKey,
+              utilization: 7500,
+              reserveAddress: anchor.web3.Keypair.generate().publicKey,
+            }
+          },
+          new anchor.BN(1000000000)
+        )
+        .accountsPartial({
+          portfolio: portfolioPda,
+          strategy: strategyPda,
+          vault: null,
+          manager: fundedManager.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([fundedManager])
+        .rpc();
+      expect.fail("Registration without a vault should fail in funded mode");
+    } catch (error) {
+      expect(error.message).to.include("VaultRequired");
+    }
+  });
+});